@@ -0,0 +1,174 @@
+use std::io::IoSlice;
+
+/// Bytes which may be split over several slices, which an [`IntoSink`](crate::IntoSink) can
+/// write as a single item.
+///
+/// This is implemented for every [`AsRef<[u8]>`](AsRef), and for sequences of those wrapped in
+/// [`Scatter`].
+pub trait Chunks {
+    /// The total number of bytes.
+    fn total_len(&self) -> usize;
+    /// Fill `dst` with the (non-empty) slices of bytes from `offset` onwards, returning the number
+    /// of slices filled.
+    ///
+    /// Fewer slices than there are in `self` may be filled if `dst` is too short.
+    fn chunks_vectored<'a>(&'a self, offset: usize, dst: &mut [IoSlice<'a>]) -> usize;
+}
+
+impl<T> Chunks for T
+where
+    T: AsRef<[u8]> + ?Sized,
+{
+    fn total_len(&self) -> usize {
+        self.as_ref().len()
+    }
+    fn chunks_vectored<'a>(&'a self, offset: usize, dst: &mut [IoSlice<'a>]) -> usize {
+        fill(std::iter::once(self.as_ref()), offset, dst)
+    }
+}
+
+/// Write a sequence of byte slices as a single item, without copying them together.
+///
+/// ```
+/// use tokio_into_sink::{IntoSinkExt as _, Scatter};
+/// use futures::SinkExt as _;
+///
+/// # futures::executor::block_on(async {
+/// let mut v = vec![];
+/// let mut sink = (&mut v).into_sink();
+/// sink.send(Scatter(("header", vec![0u8; 4], b"trailer"))).await.unwrap();
+/// drop(sink);
+/// assert_eq!(v, b"header\0\0\0\0trailer");
+/// # }) // block_on
+/// ```
+///
+/// This wrapper is required because sequences like [`Vec<u8>`] are already [`AsRef<[u8]>`](AsRef).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Scatter<T>(pub T);
+
+impl<B, const N: usize> Chunks for Scatter<[B; N]>
+where
+    B: AsRef<[u8]>,
+{
+    fn total_len(&self) -> usize {
+        self.0.iter().map(|it| it.as_ref().len()).sum()
+    }
+    fn chunks_vectored<'a>(&'a self, offset: usize, dst: &mut [IoSlice<'a>]) -> usize {
+        fill(self.0.iter().map(AsRef::as_ref), offset, dst)
+    }
+}
+
+impl<B> Chunks for Scatter<Vec<B>>
+where
+    B: AsRef<[u8]>,
+{
+    fn total_len(&self) -> usize {
+        self.0.iter().map(|it| it.as_ref().len()).sum()
+    }
+    fn chunks_vectored<'a>(&'a self, offset: usize, dst: &mut [IoSlice<'a>]) -> usize {
+        fill(self.0.iter().map(AsRef::as_ref), offset, dst)
+    }
+}
+
+impl<B> Chunks for Scatter<&[B]>
+where
+    B: AsRef<[u8]>,
+{
+    fn total_len(&self) -> usize {
+        self.0.iter().map(|it| it.as_ref().len()).sum()
+    }
+    fn chunks_vectored<'a>(&'a self, offset: usize, dst: &mut [IoSlice<'a>]) -> usize {
+        fill(self.0.iter().map(AsRef::as_ref), offset, dst)
+    }
+}
+
+macro_rules! tuple {
+    ($($ty:ident),*) => {
+        impl<$($ty),*> Chunks for Scatter<($($ty,)*)>
+        where
+            $($ty: AsRef<[u8]>,)*
+        {
+            fn total_len(&self) -> usize {
+                #[allow(non_snake_case)]
+                let ($($ty,)*) = &self.0;
+                0 $(+ $ty.as_ref().len())*
+            }
+            fn chunks_vectored<'a>(&'a self, offset: usize, dst: &mut [IoSlice<'a>]) -> usize {
+                #[allow(non_snake_case)]
+                let ($($ty,)*) = &self.0;
+                fill([$($ty.as_ref()),*].into_iter(), offset, dst)
+            }
+        }
+    };
+}
+
+tuple!(A, B);
+tuple!(A, B, C);
+tuple!(A, B, C, D);
+
+/// Fill `dst` with the non-empty parts of `slices`, skipping the first `offset` bytes.
+fn fill<'a>(
+    slices: impl Iterator<Item = &'a [u8]>,
+    mut offset: usize,
+    dst: &mut [IoSlice<'a>],
+) -> usize {
+    let mut filled = 0;
+    for slice in slices {
+        if filled == dst.len() {
+            break;
+        }
+        if offset >= slice.len() {
+            offset -= slice.len();
+            continue;
+        }
+        dst[filled] = IoSlice::new(&slice[offset..]);
+        offset = 0;
+        filled += 1;
+    }
+    filled
+}
+
+/// Copy all of the bytes in `chunks` to the end of `dst`.
+pub(crate) fn extend(dst: &mut Vec<u8>, chunks: &(impl Chunks + ?Sized)) {
+    let mut slices = [IoSlice::new(&[]); 16];
+    let mut offset = 0;
+    loop {
+        let filled = chunks.chunks_vectored(offset, &mut slices);
+        if filled == 0 {
+            break;
+        }
+        for slice in &slices[..filled] {
+            dst.extend_from_slice(slice);
+            offset += slice.len();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collect(chunks: &impl Chunks, offset: usize) -> Vec<Vec<u8>> {
+        let mut slices = [IoSlice::new(&[]); 8];
+        let filled = chunks.chunks_vectored(offset, &mut slices);
+        slices[..filled].iter().map(|it| it.to_vec()).collect()
+    }
+
+    #[test]
+    fn scatter() {
+        let item = Scatter(["ab", "", "cde", "f"]);
+        assert_eq!(item.total_len(), 6);
+        assert_eq!(collect(&item, 0), [&b"ab"[..], b"cde", b"f"]);
+        assert_eq!(collect(&item, 2), [&b"cde"[..], b"f"]);
+        assert_eq!(collect(&item, 3), [&b"de"[..], b"f"]);
+        assert_eq!(collect(&item, 6), Vec::<Vec<u8>>::new());
+
+        let item = Scatter((b"ab", vec![b'c'], "d"));
+        assert_eq!(item.total_len(), 4);
+        assert_eq!(collect(&item, 1), [&b"b"[..], b"c", b"d"]);
+
+        let mut v = vec![];
+        extend(&mut v, &item);
+        assert_eq!(v, b"abcd");
+    }
+}
@@ -0,0 +1,262 @@
+use std::io;
+
+use crate::Encoder;
+
+/// Write rows of fields as [CSV](https://www.rfc-editor.org/rfc/rfc4180).
+///
+/// Rows may be any [`IntoIterator`] of [`AsRef<str>`] fields, such as [`Vec<String>`].
+/// Fields are quoted as required.
+///
+/// See [`IntoSinkExt::into_csv_sink`](crate::IntoSinkExt::into_csv_sink), or [`CsvEncoder`] to
+/// configure the underlying sink.
+///
+/// ```
+/// use tokio_into_sink::{Csv, IntoSinkExt as _};
+/// use futures::{stream, StreamExt as _};
+/// use std::io;
+///
+/// # futures::executor::block_on(async {
+/// let mut v = vec![];
+/// let sink = (&mut v).into_csv_sink(Csv::new().header(["name", "quote"]));
+/// stream::iter([["alice", "hello, world"], ["bob", "\"hi\""]])
+///     .map(io::Result::Ok)
+///     .forward(sink)
+///     .await
+///     .unwrap();
+/// assert_eq!(v, b"name,quote\nalice,\"hello, world\"\nbob,\"\"\"hi\"\"\"\n");
+/// # }) // block_on
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Csv {
+    header: Option<Vec<String>>,
+    delimiter: u8,
+    terminator: &'static str,
+}
+
+impl Default for Csv {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Csv {
+    /// Comma-separated fields, with rows terminated by `\n`, and no header.
+    pub fn new() -> Self {
+        Self {
+            header: None,
+            delimiter: b',',
+            terminator: "\n",
+        }
+    }
+    /// Write `header` before the first row.
+    ///
+    /// Rows with a different number of fields are rejected with [`io::ErrorKind::InvalidData`].
+    pub fn header<I>(mut self, header: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.header = Some(header.into_iter().map(Into::into).collect());
+        self
+    }
+    /// Separate fields with `delimiter` instead of `,`.
+    ///
+    /// # Panics
+    /// - If `delimiter` is `"`, `\r` or `\n`.
+    pub fn delimiter(mut self, delimiter: u8) -> Self {
+        assert!(
+            !matches!(delimiter, b'"' | b'\r' | b'\n'),
+            "invalid delimiter"
+        );
+        self.delimiter = delimiter;
+        self
+    }
+    /// Terminate rows with `\r\n` instead of `\n`.
+    pub fn crlf(mut self) -> Self {
+        self.terminator = "\r\n";
+        self
+    }
+
+    fn write_row<I>(&self, row: I, dst: &mut Vec<u8>) -> io::Result<()>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let start = dst.len();
+        let mut count = 0;
+        for field in row {
+            if count > 0 {
+                dst.push(self.delimiter);
+            }
+            count += 1;
+            let field = field.as_ref().as_bytes();
+            let needs_quotes = field
+                .iter()
+                .any(|it| matches!(*it, b'"' | b'\r' | b'\n') || *it == self.delimiter);
+            match needs_quotes {
+                true => {
+                    dst.push(b'"');
+                    for byte in field {
+                        if *byte == b'"' {
+                            dst.push(b'"')
+                        }
+                        dst.push(*byte)
+                    }
+                    dst.push(b'"');
+                }
+                false => dst.extend_from_slice(field),
+            }
+        }
+        if count == 1 && dst.len() == start {
+            // a row of one empty field would otherwise be an empty line
+            dst.extend_from_slice(b"\"\"");
+        }
+        if let Some(header) = &self.header {
+            if count != header.len() {
+                dst.truncate(start);
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "row has {count} fields, but the header has {}",
+                        header.len()
+                    ),
+                ));
+            }
+        }
+        dst.extend_from_slice(self.terminator.as_bytes());
+        Ok(())
+    }
+}
+
+/// An [`Encoder`] of [`Csv`] rows, which writes the header before the first row.
+///
+/// ```
+/// use tokio_into_sink::{Csv, CsvEncoder, FramedSink, IntoSinkExt as _};
+/// use futures::SinkExt as _;
+///
+/// # futures::executor::block_on(async {
+/// let mut v = vec![];
+/// let mut sink = FramedSink::new(
+///     (&mut v).into_sink().with_coalescing(64),
+///     CsvEncoder::new(Csv::new().header(["a", "b"])),
+/// );
+/// sink.send(["1", "2"]).await.unwrap();
+/// drop(sink);
+/// assert_eq!(v, b"a,b\n1,2\n");
+/// # }) // block_on
+/// ```
+#[derive(Debug, Clone)]
+pub struct CsvEncoder {
+    csv: Csv,
+    header_written: bool,
+    /// Whether the last row was preceded by the header.
+    wrote_header: bool,
+}
+
+impl CsvEncoder {
+    /// Encode rows as configured by `csv`.
+    pub fn new(csv: Csv) -> Self {
+        Self {
+            csv,
+            header_written: false,
+            wrote_header: false,
+        }
+    }
+    /// Get a reference to the configuration.
+    pub fn csv(&self) -> &Csv {
+        &self.csv
+    }
+}
+
+impl<R> Encoder<R> for CsvEncoder
+where
+    R: IntoIterator,
+    R::Item: AsRef<str>,
+{
+    type Error = io::Error;
+
+    fn encode(&mut self, item: R, dst: &mut Vec<u8>) -> Result<(), Self::Error> {
+        let start = dst.len();
+        if let (false, Some(header)) = (self.header_written, &self.csv.header) {
+            self.csv.write_row(header, dst)?;
+        }
+        if let Err(e) = self.csv.write_row(item, dst) {
+            dst.truncate(start);
+            return Err(e);
+        }
+        self.wrote_header = !self.header_written && self.csv.header.is_some();
+        self.header_written = true;
+        Ok(())
+    }
+
+    fn rejected(&mut self) {
+        if self.wrote_header {
+            self.header_written = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{FramedSink, IntoSinkExt as _, LengthDelimited};
+    use futures::{executor::block_on, SinkExt as _};
+
+    fn encode<R>(csv: &mut CsvEncoder, row: R) -> io::Result<String>
+    where
+        R: IntoIterator,
+        R::Item: AsRef<str>,
+    {
+        let mut dst = vec![];
+        csv.encode(row, &mut dst)?;
+        Ok(String::from_utf8(dst).unwrap())
+    }
+
+    #[test]
+    fn quoting() {
+        let mut csv = CsvEncoder::new(Csv::new().delimiter(b';').crlf());
+        assert_eq!(
+            encode(&mut csv, ["a", "b,c", "d;e"]).unwrap(),
+            "a;b,c;\"d;e\"\r\n"
+        );
+        assert_eq!(
+            encode(&mut csv, ["line\nbreak", "", "\"quoted\""]).unwrap(),
+            "\"line\nbreak\";;\"\"\"quoted\"\"\"\r\n"
+        );
+        assert_eq!(encode(&mut csv, Vec::<String>::new()).unwrap(), "\r\n");
+        assert_eq!(encode(&mut csv, [""]).unwrap(), "\"\"\r\n");
+    }
+
+    #[test]
+    fn header() {
+        let mut csv = CsvEncoder::new(Csv::new().header(["a", "b"]));
+        assert!(encode(&mut csv, ["1"]).is_err());
+        assert_eq!(encode(&mut csv, ["1", "2"]).unwrap(), "a,b\n1,2\n");
+        assert_eq!(
+            encode(&mut csv, vec![String::from("3"), String::from("4")]).unwrap(),
+            "3,4\n"
+        );
+    }
+
+    #[test]
+    fn rejected() {
+        block_on(async {
+            let mut v = vec![];
+            let mut sink = FramedSink::new(
+                (&mut v)
+                    .into_sink()
+                    .with_length_delimited(LengthDelimited::new().u16().max_frame_length(10)),
+                CsvEncoder::new(Csv::new().header(["a", "b"])),
+            );
+            // misuse is rejected before the row is encoded
+            assert!(sink.start_send_unpin(["1", "2"]).is_err());
+            // too long along with the header
+            assert!(sink.send(["1234", "5678"]).await.is_err());
+            sink.send(["1", "2"]).await.unwrap();
+            sink.send(["3", "4"]).await.unwrap();
+            drop(sink);
+            assert_eq!(v, b"\x00\x08a,b\n1,2\n\x00\x043,4\n");
+        })
+    }
+}
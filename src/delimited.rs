@@ -0,0 +1,208 @@
+use std::{
+    io::{self, IoSlice},
+    sync::Arc,
+};
+
+use crate::Chunks;
+
+/// Follow each item with a delimiter, such as a newline.
+///
+/// See [`IntoSink::with_delimiter`](crate::IntoSink::with_delimiter).
+///
+/// ```
+/// use tokio_into_sink::{Delimited, IntoSinkExt as _};
+/// use futures::SinkExt as _;
+///
+/// # futures::executor::block_on(async {
+/// let mut v = vec![];
+/// let mut sink = (&mut v).into_sink().with_delimiter(Delimited::crlf());
+/// sink.send("hello").await.unwrap();
+/// sink.send("world").await.unwrap();
+/// drop(sink);
+/// assert_eq!(v, b"hello\r\nworld\r\n");
+/// # }) // block_on
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Delimited {
+    delimiter: Arc<[u8]>,
+    reject_embedded: bool,
+}
+
+impl Delimited {
+    /// Follow each item with `delimiter`.
+    ///
+    /// # Panics
+    /// - If `delimiter` is empty.
+    pub fn new(delimiter: impl AsRef<[u8]>) -> Self {
+        let delimiter = delimiter.as_ref();
+        assert!(!delimiter.is_empty(), "delimiter must not be empty");
+        Self {
+            delimiter: delimiter.into(),
+            reject_embedded: false,
+        }
+    }
+    /// Follow each item with `\n`.
+    pub fn lf() -> Self {
+        Self::new("\n")
+    }
+    /// Follow each item with `\r\n`.
+    pub fn crlf() -> Self {
+        Self::new("\r\n")
+    }
+    /// Reject items which contain the delimiter with [`io::ErrorKind::InvalidData`],
+    /// rather than writing them.
+    ///
+    /// This includes items which end with the start of the delimiter, which would otherwise be
+    /// split early.
+    pub fn reject_embedded(mut self, reject: bool) -> Self {
+        self.reject_embedded = reject;
+        self
+    }
+    /// The delimiter to write after `item`.
+    pub(crate) fn trailer(&self, item: &(impl Chunks + ?Sized)) -> io::Result<Arc<[u8]>> {
+        // an item ending with the start of the delimiter would also end early
+        let tail = &self.delimiter[..self.delimiter.len() - 1];
+        if self.reject_embedded && contains(&Followed { item, tail }, &self.delimiter) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "item contains the delimiter",
+            ));
+        }
+        Ok(self.delimiter.clone())
+    }
+}
+
+/// `item`, followed by `tail`.
+struct Followed<'a, T: ?Sized> {
+    item: &'a T,
+    tail: &'a [u8],
+}
+
+impl<T> Chunks for Followed<'_, T>
+where
+    T: Chunks + ?Sized,
+{
+    fn total_len(&self) -> usize {
+        self.item.total_len() + self.tail.len()
+    }
+    fn chunks_vectored<'a>(&'a self, offset: usize, dst: &mut [IoSlice<'a>]) -> usize {
+        let filled = self.item.chunks_vectored(offset, dst);
+        // `item` stops early only if `dst` is full
+        let offset = offset.saturating_sub(self.item.total_len());
+        match dst.get_mut(filled) {
+            Some(slot) if offset < self.tail.len() => {
+                *slot = IoSlice::new(&self.tail[offset..]);
+                filled + 1
+            }
+            _ => filled,
+        }
+    }
+}
+
+/// Whether `needle` occurs anywhere in `haystack`, including across slice boundaries.
+fn contains(haystack: &(impl Chunks + ?Sized), needle: &[u8]) -> bool {
+    let mut slices = [IoSlice::new(&[]); 16];
+    let mut offset = 0;
+    // the tail of the previous slices, which may start a match
+    let mut carry = Vec::new();
+    loop {
+        let filled = haystack.chunks_vectored(offset, &mut slices);
+        if filled == 0 {
+            return false;
+        }
+        for slice in &slices[..filled] {
+            offset += slice.len();
+            let split = slice.len().min(needle.len() - 1);
+            carry.extend_from_slice(&slice[..split]);
+            if find(&carry, needle) || find(slice, needle) {
+                return true;
+            }
+            carry.extend_from_slice(&slice[split..]);
+            carry.drain(..carry.len().saturating_sub(needle.len() - 1));
+        }
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|it| it == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{test_util::Mock, IntoSinkExt as _, Scatter, Unwritten};
+    use futures::{executor::block_on, SinkExt as _};
+
+    #[test]
+    fn contains() {
+        assert!(super::contains("ab\r\ncd", b"\r\n"));
+        assert!(!super::contains("ab\rcd", b"\r\n"));
+        assert!(super::contains(&Scatter(["ab\r", "\ncd"]), b"\r\n"));
+        assert!(super::contains(&Scatter(["a", "b", "c"]), b"abc"));
+        assert!(!super::contains(&Scatter(["a", "b", "d"]), b"abc"));
+        let followed = |item| Followed { item, tail: b"-" };
+        assert!(super::contains(&followed("a-"), b"--"));
+        assert!(!super::contains(&followed("-a"), b"--"));
+        let delimited = Delimited::new("--").reject_embedded(true);
+        assert!(delimited.trailer("a-").is_err());
+        assert!(delimited.trailer("-a").is_ok());
+    }
+
+    #[test]
+    fn reject_embedded() {
+        block_on(async {
+            let mut v = vec![];
+            let mut sink = (&mut v)
+                .into_sink()
+                .with_delimiter(Delimited::lf().reject_embedded(true));
+            sink.send("hello").await.unwrap();
+            let error = sink.send("hello\nworld").await.unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+            sink.send("").await.unwrap();
+            drop(sink);
+            assert_eq!(v, b"hello\n\n");
+        })
+    }
+
+    #[test]
+    fn with_coalescing() {
+        block_on(async {
+            let mut v = vec![];
+            let mut sink = (&mut v)
+                .into_sink_with_capacity(4)
+                .with_vectored()
+                .with_coalescing(4)
+                .with_delimiter(Delimited::new("--"));
+            for item in ["a", "bcdef", "g"] {
+                sink.feed(item).await.unwrap();
+            }
+            sink.close().await.unwrap();
+            drop(sink);
+            assert_eq!(v, b"a--bcdef--g--");
+        })
+    }
+
+    #[test]
+    fn unwritten() {
+        block_on(async {
+            let mut sink = Mock::new()
+                .fail_after(4)
+                .into_sink()
+                .with_delimiter(Delimited::crlf())
+                .with_send_error();
+            let (_, unwritten) = sink.send("abc").await.unwrap_err().into_parts();
+            let unwritten = unwritten.unwrap();
+            assert_eq!(
+                unwritten,
+                Unwritten::Item {
+                    item: "abc",
+                    written: 3,
+                    header: vec![],
+                    trailer: b"\n".to_vec(),
+                }
+            );
+            assert_eq!(unwritten.remaining(), b"");
+        })
+    }
+}
@@ -0,0 +1,525 @@
+use std::{
+    collections::VecDeque,
+    io::{self, IoSlice},
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use futures_sink::Sink;
+use tokio::io::AsyncWrite;
+
+use crate::{misuse, Chunks, State, MAX_IO_SLICES};
+
+/// What a [`FanOut`] does when one of its writers fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FailurePolicy {
+    /// Return the error from the sink.
+    ///
+    /// The writer is kept, and is retried if the sink is used again.
+    FailAll,
+    /// Drop the writer, and continue with the rest.
+    ///
+    /// The error is returned if it would leave no writers.
+    /// Each item is done with once any writer has written it.
+    DropFailed,
+    /// Drop the writer, and continue with the rest, as long as at least this many remain.
+    ///
+    /// The error is returned if it would leave fewer.
+    /// Each item is done with once this many writers have written it.
+    Quorum(usize),
+}
+
+/// A [`Sink`] which writes each item to several writers.
+///
+/// Each writer writes at its own pace.
+/// Under [`FailurePolicy::FailAll`], an item is done with once every writer has written it,
+/// so a slow writer holds up the rest once the sink's capacity is reached.
+/// Otherwise, an item is done with once enough writers have written it, and slower writers
+/// catch up from the buffer.
+/// A writer which falls more than the sink's capacity behind is dropped with
+/// [`io::ErrorKind::TimedOut`], so a writer which hangs cannot hold up the rest.
+///
+/// Likewise, flushing and closing complete once enough writers have written every item and
+/// flushed or shut down.
+/// Slower writers are left to catch up if the sink is used again.
+///
+/// ```
+/// use tokio_into_sink::{FailurePolicy, FanOut};
+/// use futures::{stream, StreamExt as _};
+/// use std::io;
+///
+/// # futures::executor::block_on(async {
+/// let mut sink = FanOut::new([vec![], vec![]], FailurePolicy::FailAll);
+/// stream::iter(["hello", "world"])
+///     .map(io::Result::Ok)
+///     .forward(&mut sink)
+///     .await
+///     .unwrap();
+/// for writer in sink.into_inner() {
+///     assert_eq!(writer.unwrap(), b"helloworld");
+/// }
+/// # }) // block_on
+/// ```
+#[derive(Debug)]
+pub struct FanOut<W, Item> {
+    writers: Vec<Slot<W>>,
+    buffer: VecDeque<Item>,
+    capacity: usize,
+    /// The number of writers which must remain.
+    required: usize,
+    state: State,
+}
+
+// Neither writers nor items are pinned.
+impl<W, Item> Unpin for FanOut<W, Item> {}
+
+#[derive(Debug)]
+enum Slot<W> {
+    Live {
+        writer: W,
+        /// The item in `buffer` which is being written.
+        index: usize,
+        /// How much of that item has been written.
+        offset: usize,
+    },
+    Failed(io::Error),
+}
+
+impl<W, Item> FanOut<W, Item> {
+    /// Write each item to all of `writers`, handling failures according to `policy`.
+    ///
+    /// # Panics
+    /// - If `writers` is empty.
+    /// - If `policy` is a [`FailurePolicy::Quorum`] of zero, or of more than the number of
+    ///   `writers`.
+    pub fn new(writers: impl IntoIterator<Item = W>, policy: FailurePolicy) -> Self {
+        let writers = writers
+            .into_iter()
+            .map(|writer| Slot::Live {
+                writer,
+                index: 0,
+                offset: 0,
+            })
+            .collect::<Vec<_>>();
+        assert!(!writers.is_empty(), "writers must not be empty");
+        let required = match policy {
+            FailurePolicy::FailAll => writers.len(),
+            FailurePolicy::DropFailed => 1,
+            FailurePolicy::Quorum(quorum) => {
+                assert!(
+                    (1..=writers.len()).contains(&quorum),
+                    "quorum must be between one and the number of writers"
+                );
+                quorum
+            }
+        };
+        Self {
+            writers,
+            buffer: VecDeque::new(),
+            capacity: 1,
+            required,
+            state: State::Open,
+        }
+    }
+    /// Accept up to `capacity` items which are not yet done with before [`Sink::poll_ready`]
+    /// waits on the writers.
+    ///
+    /// # Panics
+    /// - If `capacity` is zero.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be non-zero");
+        self.capacity = capacity;
+        self
+    }
+    /// Get a reference to the writer at `ix`, if it has not been dropped.
+    pub fn get(&self, ix: usize) -> Option<&W> {
+        match self.writers.get(ix)? {
+            Slot::Live { writer, .. } => Some(writer),
+            Slot::Failed(_) => None,
+        }
+    }
+    /// Get a mutable reference to the writer at `ix`, if it has not been dropped.
+    ///
+    /// Care should be taken not to write to the writer directly while items are outstanding.
+    pub fn get_mut(&mut self, ix: usize) -> Option<&mut W> {
+        match self.writers.get_mut(ix)? {
+            Slot::Live { writer, .. } => Some(writer),
+            Slot::Failed(_) => None,
+        }
+    }
+    /// The error which caused the writer at `ix` to be dropped, if it has been.
+    pub fn error(&self, ix: usize) -> Option<&io::Error> {
+        match self.writers.get(ix)? {
+            Slot::Live { .. } => None,
+            Slot::Failed(error) => Some(error),
+        }
+    }
+    /// The number of writers which have not been dropped.
+    pub fn live(&self) -> usize {
+        self.writers
+            .iter()
+            .filter(|it| matches!(it, Slot::Live { .. }))
+            .count()
+    }
+    /// Consume this sink, returning each writer, or the error which caused it to be dropped.
+    ///
+    /// Any items which have not been written are lost.
+    pub fn into_inner(self) -> Vec<Result<W, io::Error>> {
+        self.writers
+            .into_iter()
+            .map(|it| match it {
+                Slot::Live { writer, .. } => Ok(writer),
+                Slot::Failed(error) => Err(error),
+            })
+            .collect()
+    }
+}
+
+impl<W, Item> FanOut<W, Item>
+where
+    W: AsyncWrite + Unpin,
+    Item: Chunks,
+{
+    /// Handle `error` from the writer at `ix`, dropping it if the policy allows.
+    fn fail(&mut self, ix: usize, error: io::Error) -> io::Result<()> {
+        if self.live() <= self.required {
+            return Err(error);
+        }
+        self.writers[ix] = Slot::Failed(error);
+        Ok(())
+    }
+
+    /// The number of items in `buffer` which fewer than `required` writers have written.
+    fn unacked(&self) -> usize {
+        let mut indices = self
+            .writers
+            .iter()
+            .filter_map(|it| match it {
+                Slot::Live { index, .. } => Some(*index),
+                Slot::Failed(_) => None,
+            })
+            .collect::<Vec<_>>();
+        indices.sort_unstable_by(|a, b| b.cmp(a));
+        // there are always at least `required` live writers
+        self.buffer.len() - indices[self.required - 1]
+    }
+
+    /// Write `buffer` to every writer, until they have all written it.
+    ///
+    /// Writers which fall more than `capacity` items behind the rest are dropped.
+    fn poll_write_buffer(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        for ix in 0..self.writers.len() {
+            let Slot::Live {
+                writer,
+                index,
+                offset,
+            } = &mut self.writers[ix]
+            else {
+                continue;
+            };
+            let mut writer = Pin::new(writer);
+            while *index < self.buffer.len() {
+                let mut slices = [IoSlice::new(&[]); MAX_IO_SLICES];
+                let max = match writer.is_write_vectored() {
+                    true => MAX_IO_SLICES,
+                    false => 1,
+                };
+                let mut len = 0;
+                let mut from = *offset;
+                for item in self.buffer.range(*index..) {
+                    if len == max {
+                        break;
+                    }
+                    len += item.chunks_vectored(from, &mut slices[len..max]);
+                    from = 0;
+                }
+                let res = match writer.as_mut().poll_write_vectored(cx, &slices[..len]) {
+                    Poll::Ready(Ok(0)) => Err(io::ErrorKind::WriteZero.into()),
+                    Poll::Ready(res) => res,
+                    Poll::Pending => break,
+                };
+                let mut written = match res {
+                    Ok(it) => it,
+                    Err(e) => {
+                        self.fail(ix, e)?;
+                        break;
+                    }
+                };
+                // `buffer` never contains empty items
+                while written > 0 {
+                    let remaining = self.buffer[*index].total_len() - *offset;
+                    if written < remaining {
+                        *offset += written;
+                        break;
+                    }
+                    written -= remaining;
+                    *index += 1;
+                    *offset = 0;
+                }
+            }
+        }
+        let acked = self.buffer.len() - self.unacked();
+        for ix in 0..self.writers.len() {
+            match self.writers[ix] {
+                Slot::Live { index, .. } if index + self.capacity < acked => {
+                    let error = io::Error::new(io::ErrorKind::TimedOut, "writer fell behind");
+                    self.fail(ix, error)?;
+                }
+                _ => {}
+            }
+        }
+        let done = self
+            .writers
+            .iter()
+            .filter_map(|it| match it {
+                Slot::Live { index, .. } => Some(*index),
+                Slot::Failed(_) => None,
+            })
+            .min()
+            .unwrap_or(self.buffer.len());
+        self.buffer.drain(..done);
+        for slot in &mut self.writers {
+            if let Slot::Live { index, .. } = slot {
+                *index -= done;
+            }
+        }
+        // live writers which have not caught up stopped on `Pending`
+        let pending = self.writers.iter().any(|it| match it {
+            Slot::Live { index, .. } => *index < self.buffer.len(),
+            Slot::Failed(_) => false,
+        });
+        match pending {
+            true => Poll::Pending,
+            false => Poll::Ready(Ok(())),
+        }
+    }
+
+    /// Call `op` on every writer which has written `buffer`, until it has completed for
+    /// `required` of them.
+    fn poll_each(
+        &mut self,
+        cx: &mut Context<'_>,
+        mut op: impl FnMut(Pin<&mut W>, &mut Context<'_>) -> Poll<io::Result<()>>,
+    ) -> Poll<io::Result<()>> {
+        let mut completed = 0;
+        for ix in 0..self.writers.len() {
+            let Slot::Live { writer, index, .. } = &mut self.writers[ix] else {
+                continue;
+            };
+            if *index < self.buffer.len() {
+                continue;
+            }
+            match op(Pin::new(writer), cx) {
+                Poll::Ready(Ok(())) => completed += 1,
+                Poll::Ready(Err(e)) => self.fail(ix, e)?,
+                Poll::Pending => {}
+            }
+        }
+        match completed >= self.required {
+            true => Poll::Ready(Ok(())),
+            false => Poll::Pending,
+        }
+    }
+}
+
+impl<W, Item> Sink<Item> for FanOut<W, Item>
+where
+    W: AsyncWrite + Unpin,
+    Item: Chunks,
+{
+    type Error = io::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+        if let State::Closing | State::Closed = this.state {
+            return Poll::Ready(Err(misuse("poll_ready called after poll_close")));
+        }
+        while this.unacked() >= this.capacity {
+            let poll = this.poll_write_buffer(cx)?;
+            if poll.is_pending() && this.unacked() >= this.capacity {
+                return Poll::Pending;
+            }
+        }
+        this.state = State::Ready;
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: Item) -> Result<(), Self::Error> {
+        let this = self.get_mut();
+        match this.state {
+            State::Ready => this.state = State::Open,
+            State::Open => return Err(misuse("start_send called without poll_ready")),
+            State::Closing | State::Closed => {
+                return Err(misuse("start_send called after poll_close"))
+            }
+        }
+        if item.total_len() > 0 {
+            this.buffer.push_back(item);
+        }
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+        // slower writers needn't have caught up
+        let _ = this.poll_write_buffer(cx)?;
+        this.poll_each(cx, |writer, cx| writer.poll_flush(cx))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+        match this.state {
+            State::Closed => return Poll::Ready(Ok(())),
+            _ => this.state = State::Closing,
+        }
+        let _ = this.poll_write_buffer(cx)?;
+        ready!(this.poll_each(cx, |writer, cx| writer.poll_shutdown(cx)))?;
+        this.state = State::Closed;
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_util::Mock;
+    use futures::{executor::block_on, SinkExt as _};
+
+    #[test]
+    fn cursors() {
+        block_on(async {
+            let mut sink = FanOut::new(
+                [Mock::new().limit(2), Mock::new().limit(3)],
+                FailurePolicy::FailAll,
+            )
+            .with_capacity(2);
+            for item in ["hello", "", "world", "!"] {
+                sink.feed(item).await.unwrap();
+            }
+            sink.close().await.unwrap();
+            for ix in 0..2 {
+                assert_eq!(sink.get(ix).unwrap().written(), b"helloworld!");
+            }
+        })
+    }
+
+    #[test]
+    fn fail_all() {
+        block_on(async {
+            let mut sink = FanOut::new(
+                [Mock::new(), Mock::new().limit(2).fail_after(3)],
+                FailurePolicy::FailAll,
+            );
+            let error = sink.send("hello").await.unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::BrokenPipe);
+            // the writer is kept, along with its progress
+            assert_eq!(sink.live(), 2);
+            sink.get_mut(1).unwrap().ok = usize::MAX;
+            sink.flush().await.unwrap();
+            assert_eq!(sink.get(1).unwrap().written(), b"hello");
+        })
+    }
+
+    #[test]
+    fn drop_failed() {
+        block_on(async {
+            let mut sink = FanOut::new(
+                [Mock::new().limit(2).fail_after(3), Mock::new()],
+                FailurePolicy::DropFailed,
+            );
+            sink.send("hello").await.unwrap();
+            sink.send("world").await.unwrap();
+            assert!(sink.get(0).is_none());
+            assert_eq!(sink.error(0).unwrap().kind(), io::ErrorKind::BrokenPipe);
+            assert_eq!(sink.get(1).unwrap().written(), b"helloworld");
+            sink.get_mut(1).unwrap().ok = 0;
+            let error = sink.send("!").await.unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::BrokenPipe);
+            assert_eq!(sink.live(), 1);
+        })
+    }
+
+    #[test]
+    fn quorum() {
+        block_on(async {
+            let mut sink = FanOut::new(
+                [
+                    Mock::new().fail_after(5),
+                    Mock::new().fail_after(10),
+                    Mock::new(),
+                ],
+                FailurePolicy::Quorum(2),
+            );
+            sink.send("hello").await.unwrap();
+            sink.send("world").await.unwrap();
+            assert_eq!(sink.live(), 2);
+            sink.send("!").await.unwrap_err();
+            let writers = sink.into_inner();
+            assert!(writers[0].is_err());
+            for writer in &writers[1..] {
+                assert_eq!(writer.as_ref().unwrap().written(), b"helloworld");
+            }
+        })
+    }
+
+    #[test]
+    fn misuse() {
+        let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+        let mut sink = FanOut::new([Mock::new()], FailurePolicy::FailAll);
+        assert!(sink.start_send_unpin("a").is_err());
+        assert!(matches!(
+            sink.poll_ready_unpin(&mut cx),
+            Poll::Ready(Ok(()))
+        ));
+        sink.start_send_unpin("a").unwrap();
+        assert!(sink.start_send_unpin("b").is_err());
+        assert!(matches!(
+            sink.poll_close_unpin(&mut cx),
+            Poll::Ready(Ok(()))
+        ));
+        assert!(matches!(
+            sink.poll_ready_unpin(&mut cx),
+            Poll::Ready(Err(_))
+        ));
+        assert_eq!(sink.get(0).unwrap().written(), b"a");
+    }
+
+    #[test]
+    fn stuck() {
+        let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+        let mut sink = FanOut::new(
+            [Mock::new(), Mock::new(), Mock::stuck()],
+            FailurePolicy::Quorum(2),
+        )
+        .with_capacity(2);
+        for item in ["a", "b"] {
+            assert!(matches!(
+                sink.poll_ready_unpin(&mut cx),
+                Poll::Ready(Ok(()))
+            ));
+            sink.start_send_unpin(item).unwrap();
+        }
+        // a quorum has written both items, so the stuck writer doesn't hold up the rest
+        assert!(matches!(
+            sink.poll_ready_unpin(&mut cx),
+            Poll::Ready(Ok(()))
+        ));
+        assert_eq!(sink.live(), 3);
+        sink.start_send_unpin("c").unwrap();
+        // until it falls more than two items behind
+        assert!(matches!(
+            sink.poll_flush_unpin(&mut cx),
+            Poll::Ready(Ok(()))
+        ));
+        assert_eq!(sink.live(), 2);
+        assert_eq!(sink.error(2).unwrap().kind(), io::ErrorKind::TimedOut);
+        assert!(matches!(
+            sink.poll_close_unpin(&mut cx),
+            Poll::Ready(Ok(()))
+        ));
+        for ix in 0..2 {
+            assert_eq!(sink.get(ix).unwrap().written(), b"abc");
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_sink::Sink;
+use pin_project_lite::pin_project;
+use tokio::io::AsyncWrite;
+
+use crate::IntoSink;
+
+/// Convert messages to bytes for a [`FramedSink`].
+///
+/// This mirrors `tokio_util::codec::Encoder`, but encodes into a [`Vec<u8>`].
+pub trait Encoder<Item> {
+    /// The error returned by [`encode`](Encoder::encode) and the [`FramedSink`].
+    type Error: From<io::Error>;
+    /// Append the encoded form of `item` to `dst`.
+    fn encode(&mut self, item: Item, dst: &mut Vec<u8>) -> Result<(), Self::Error>;
+    /// Called when the bytes from the last call to [`encode`](Encoder::encode) were rejected by
+    /// the sink, so will not be written.
+    ///
+    /// Encoders which keep track of what they have written should undo that call.
+    fn rejected(&mut self) {}
+}
+
+impl<T, Item> Encoder<Item> for &mut T
+where
+    T: Encoder<Item> + ?Sized,
+{
+    type Error = T::Error;
+    fn encode(&mut self, item: Item, dst: &mut Vec<u8>) -> Result<(), Self::Error> {
+        T::encode(self, item, dst)
+    }
+    fn rejected(&mut self) {
+        T::rejected(self)
+    }
+}
+
+pin_project! {
+    /// A [`Sink`] of messages, which are [encoded](Encoder) and written through an [`IntoSink`].
+    ///
+    /// See [`IntoSinkExt::into_framed_sink`](crate::IntoSinkExt::into_framed_sink).
+    #[derive(Debug)]
+    pub struct FramedSink<W, En> {
+        #[pin]
+        inner: IntoSink<W, Vec<u8>>,
+        encoder: En,
+        // Reused for each message.
+        dst: Vec<u8>,
+    }
+}
+
+impl<W, En> FramedSink<W, En> {
+    /// Encode messages with `encoder`, and write them through `inner`.
+    ///
+    /// Each message is sent to `inner` as a single item, so its
+    /// [capacity](IntoSink::capacity) and other configuration apply to messages.
+    /// Messages are encoded into a reused buffer, and copied from there into `inner`'s
+    /// [coalescing](IntoSink::with_coalescing) buffer if they fit, so that small messages may be
+    /// sent without allocating.
+    pub fn new(inner: IntoSink<W, Vec<u8>>, encoder: En) -> Self {
+        Self {
+            inner,
+            encoder,
+            dst: Vec::new(),
+        }
+    }
+    /// Get a reference to the encoder.
+    pub fn encoder(&self) -> &En {
+        &self.encoder
+    }
+    /// Get a mutable reference to the encoder.
+    pub fn encoder_mut(&mut self) -> &mut En {
+        &mut self.encoder
+    }
+    /// Get a reference to the underlying sink.
+    pub fn get_ref(&self) -> &IntoSink<W, Vec<u8>> {
+        &self.inner
+    }
+    /// Get a mutable reference to the underlying sink.
+    pub fn get_mut(&mut self) -> &mut IntoSink<W, Vec<u8>> {
+        &mut self.inner
+    }
+    /// Get a pinned mutable reference to the underlying sink.
+    pub fn get_pin_mut(self: Pin<&mut Self>) -> Pin<&mut IntoSink<W, Vec<u8>>> {
+        self.project().inner
+    }
+    /// Consume this sink, returning the underlying sink and encoder.
+    pub fn into_parts(self) -> (IntoSink<W, Vec<u8>>, En) {
+        (self.inner, self.encoder)
+    }
+}
+
+impl<W, En, Item> Sink<Item> for FramedSink<W, En>
+where
+    W: AsyncWrite,
+    En: Encoder<Item>,
+{
+    type Error = En::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.project().inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: Item) -> Result<(), Self::Error> {
+        let mut this = self.project();
+        // don't encode messages which would be rejected anyway
+        this.inner.as_ref().get_ref().check_ready()?;
+        this.dst.clear();
+        this.encoder.encode(item, this.dst)?;
+        if let Err(e) = this.inner.as_mut().start_send_slice(this.dst) {
+            this.encoder.rejected();
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.project().inner.poll_flush(cx).map_err(Into::into)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.project().inner.poll_close(cx).map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{test_util::Mock, IntoSinkExt as _};
+    use futures::{executor::block_on, SinkExt as _};
+
+    /// Write numbers as decimal, separated by commas.
+    struct Decimal;
+
+    impl Encoder<u32> for Decimal {
+        type Error = io::Error;
+        fn encode(&mut self, item: u32, dst: &mut Vec<u8>) -> Result<(), Self::Error> {
+            dst.extend_from_slice(format!("{item},").as_bytes());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn encode() {
+        block_on(async {
+            let mut v = vec![];
+            let mut sink = (&mut v).into_framed_sink(Decimal);
+            for item in [1, 20, 300] {
+                sink.feed(item).await.unwrap();
+            }
+            sink.close().await.unwrap();
+            drop(sink);
+            assert_eq!(v, b"1,20,300,");
+        })
+    }
+
+    #[test]
+    fn coalescing() {
+        block_on(async {
+            let mut writer = Mock::new().vectored();
+            let mut sink = FramedSink::new((&mut writer).into_sink().with_coalescing(16), Decimal);
+            for item in [1, 20, 300, 4000] {
+                sink.feed(item).await.unwrap();
+            }
+            sink.close().await.unwrap();
+            drop(sink);
+            assert_eq!(writer.written(), b"1,20,300,4000,");
+            assert_eq!(writer.calls, 1);
+        })
+    }
+}
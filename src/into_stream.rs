@@ -0,0 +1,180 @@
+use std::{
+    io,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use futures_core::{FusedStream, Stream};
+use pin_project_lite::pin_project;
+use tokio::io::{AsyncRead, ReadBuf};
+
+use crate::{LengthDelimited, LengthDelimitedStream};
+
+/// Use an [`AsyncRead`] as a [`Stream`]`<Item = io::Result<Vec<u8>>>` - the counterpart to
+/// [`IntoSinkExt`](crate::IntoSinkExt).
+///
+/// ```
+/// use tokio_into_sink::IntoStreamExt as _;
+/// use futures::TryStreamExt as _;
+///
+/// # futures::executor::block_on(async {
+/// let chunks = b"hello world"
+///     .as_slice()
+///     .into_stream(5)
+///     .try_collect::<Vec<_>>()
+///     .await
+///     .unwrap();
+/// assert_eq!(chunks, [&b"hello"[..], b" worl", b"d"]);
+/// # }) // block_on
+/// ```
+pub trait IntoStreamExt: AsyncRead {
+    /// Read chunks of at most `chunk_size` bytes from this reader, until it reaches EOF.
+    ///
+    /// # Panics
+    /// - If `chunk_size` is zero.
+    fn into_stream(self, chunk_size: usize) -> IntoStream<Self>
+    where
+        Self: Sized;
+    /// Read frames written with [`LengthDelimited`] framing from this reader, until it reaches EOF.
+    fn into_length_delimited_stream(self, codec: LengthDelimited) -> LengthDelimitedStream<Self>
+    where
+        Self: Sized;
+}
+
+impl<R> IntoStreamExt for R
+where
+    R: AsyncRead,
+{
+    fn into_stream(self, chunk_size: usize) -> IntoStream<Self>
+    where
+        Self: Sized,
+    {
+        assert!(chunk_size > 0, "chunk_size must be non-zero");
+        IntoStream {
+            reader: self,
+            chunk_size,
+            chunk: Vec::new(),
+            eof: false,
+        }
+    }
+    fn into_length_delimited_stream(self, codec: LengthDelimited) -> LengthDelimitedStream<Self>
+    where
+        Self: Sized,
+    {
+        LengthDelimitedStream::new(self, codec)
+    }
+}
+
+pin_project! {
+    /// See [`IntoStreamExt::into_stream`].
+    #[derive(Debug)]
+    pub struct IntoStream<R> {
+        #[pin]
+        reader: R,
+        chunk_size: usize,
+        // Read into, and reused for every chunk.
+        chunk: Vec<u8>,
+        eof: bool,
+    }
+}
+
+impl<R> IntoStream<R> {
+    /// Get a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+    /// Get a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+    /// Get a pinned mutable reference to the underlying reader.
+    pub fn get_pin_mut(self: Pin<&mut Self>) -> Pin<&mut R> {
+        self.project().reader
+    }
+    /// Consume this stream, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R> Stream for IntoStream<R>
+where
+    R: AsyncRead,
+{
+    type Item = io::Result<Vec<u8>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        if *this.eof {
+            return Poll::Ready(None);
+        }
+        if this.chunk.is_empty() {
+            *this.chunk = vec![0; *this.chunk_size];
+        }
+        let mut buf = ReadBuf::new(this.chunk);
+        ready!(this.reader.poll_read(cx, &mut buf))?;
+        match buf.filled() {
+            [] => {
+                *this.eof = true;
+                *this.chunk = Vec::new();
+                Poll::Ready(None)
+            }
+            filled => Poll::Ready(Some(Ok(filled.to_vec()))),
+        }
+    }
+}
+
+impl<R> FusedStream for IntoStream<R>
+where
+    R: AsyncRead,
+{
+    fn is_terminated(&self) -> bool {
+        self.eof
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{test_util::Stutter, IntoSinkExt as _};
+    use futures::{executor::block_on, StreamExt as _, TryStreamExt as _};
+
+    #[test]
+    fn round_trip() {
+        block_on(async {
+            let mut v = vec![];
+            b"hello world"
+                .as_slice()
+                .into_stream(4)
+                .forward((&mut v).into_sink())
+                .await
+                .unwrap();
+            assert_eq!(v, b"hello world");
+        })
+    }
+
+    #[test]
+    fn fused() {
+        block_on(async {
+            let mut stream = b"".as_slice().into_stream(4);
+            assert!(stream.next().await.is_none());
+            assert!(stream.is_terminated());
+            assert!(stream.next().await.is_none());
+        })
+    }
+
+    #[test]
+    fn pending_reads() {
+        block_on(async {
+            let chunks = Stutter::new(b"abc")
+                .into_stream(4)
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap();
+            assert_eq!(chunks, [b"a", b"b", b"c"]);
+            // chunks are copied out of the read buffer
+            assert!(chunks.iter().all(|it| it.capacity() == 1));
+        })
+    }
+}
@@ -0,0 +1,481 @@
+use std::{
+    io,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use futures_core::{FusedStream, Stream};
+use pin_project_lite::pin_project;
+use tokio::io::{AsyncRead, ReadBuf};
+
+/// The encoding of the length header for [`LengthDelimited`] framing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LengthField {
+    U16,
+    U32,
+    U64,
+    /// An unsigned [LEB128](https://en.wikipedia.org/wiki/LEB128) integer,
+    /// as used by protobuf.
+    Varint,
+}
+
+/// Prefix each item with its length.
+///
+/// Used by [`IntoSink::with_length_delimited`](crate::IntoSink::with_length_delimited) to write
+/// frames, and [`IntoStreamExt::into_length_delimited_stream`](crate::IntoStreamExt::into_length_delimited_stream)
+/// to read them.
+///
+/// ```
+/// use tokio_into_sink::{IntoSinkExt as _, IntoStreamExt as _, LengthDelimited};
+/// use futures::{SinkExt as _, TryStreamExt as _};
+///
+/// # futures::executor::block_on(async {
+/// let codec = LengthDelimited::new().u16().little_endian();
+/// let mut v = vec![];
+/// let mut sink = (&mut v).into_sink().with_length_delimited(codec);
+/// sink.send("hello").await.unwrap();
+/// drop(sink);
+/// assert_eq!(v, b"\x05\x00hello");
+///
+/// let frames = v
+///     .as_slice()
+///     .into_length_delimited_stream(codec)
+///     .try_collect::<Vec<_>>()
+///     .await
+///     .unwrap();
+/// assert_eq!(frames, [b"hello"]);
+/// # }) // block_on
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LengthDelimited {
+    field: LengthField,
+    big_endian: bool,
+    max_frame_length: usize,
+}
+
+impl Default for LengthDelimited {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LengthDelimited {
+    /// A big-endian [`u32`](LengthField::U32) length, with a maximum frame length of 8MiB.
+    pub fn new() -> Self {
+        Self {
+            field: LengthField::U32,
+            big_endian: true,
+            max_frame_length: 8 * 1024 * 1024,
+        }
+    }
+    /// Use `field` to encode the length.
+    pub fn length_field(mut self, field: LengthField) -> Self {
+        self.field = field;
+        self
+    }
+    /// Shorthand for [`length_field(LengthField::U16)`](Self::length_field).
+    pub fn u16(self) -> Self {
+        self.length_field(LengthField::U16)
+    }
+    /// Shorthand for [`length_field(LengthField::U32)`](Self::length_field).
+    pub fn u32(self) -> Self {
+        self.length_field(LengthField::U32)
+    }
+    /// Shorthand for [`length_field(LengthField::U64)`](Self::length_field).
+    pub fn u64(self) -> Self {
+        self.length_field(LengthField::U64)
+    }
+    /// Shorthand for [`length_field(LengthField::Varint)`](Self::length_field).
+    pub fn varint(self) -> Self {
+        self.length_field(LengthField::Varint)
+    }
+    /// Write fixed-width lengths with the most significant byte first (the default).
+    pub fn big_endian(mut self) -> Self {
+        self.big_endian = true;
+        self
+    }
+    /// Write fixed-width lengths with the least significant byte first.
+    pub fn little_endian(mut self) -> Self {
+        self.big_endian = false;
+        self
+    }
+    /// Reject frames longer than `max_frame_length` bytes, not including the header.
+    pub fn max_frame_length(mut self, max_frame_length: usize) -> Self {
+        self.max_frame_length = max_frame_length;
+        self
+    }
+
+    fn check(&self, len: u64) -> io::Result<usize> {
+        let max = match self.field {
+            LengthField::U16 => u16::MAX as u64,
+            LengthField::U32 => u32::MAX as u64,
+            LengthField::U64 | LengthField::Varint => u64::MAX,
+        };
+        match usize::try_from(len) {
+            Ok(it) if len <= max && it <= self.max_frame_length => Ok(it),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "frame of length {len} exceeds the maximum of {}",
+                    self.max_frame_length
+                ),
+            )),
+        }
+    }
+
+    /// The header for a frame of `len` bytes.
+    pub(crate) fn header(&self, len: usize) -> io::Result<Header> {
+        self.check(len as u64)?;
+        let len = len as u64;
+        let mut header = Header::default();
+        match (self.field, self.big_endian) {
+            (LengthField::U16, true) => header.push(&(len as u16).to_be_bytes()),
+            (LengthField::U16, false) => header.push(&(len as u16).to_le_bytes()),
+            (LengthField::U32, true) => header.push(&(len as u32).to_be_bytes()),
+            (LengthField::U32, false) => header.push(&(len as u32).to_le_bytes()),
+            (LengthField::U64, true) => header.push(&len.to_be_bytes()),
+            (LengthField::U64, false) => header.push(&len.to_le_bytes()),
+            (LengthField::Varint, _) => {
+                let mut rest = len;
+                loop {
+                    let byte = (rest & 0x7f) as u8;
+                    rest >>= 7;
+                    match rest {
+                        0 => break header.push(&[byte]),
+                        _ => header.push(&[byte | 0x80]),
+                    }
+                }
+            }
+        }
+        Ok(header)
+    }
+
+    /// Parse a header from the start of `src`, returning the length of the header and the frame,
+    /// or [`None`] if more bytes are needed.
+    fn decode_header(&self, src: &[u8]) -> io::Result<Option<(usize, usize)>> {
+        fn fixed<const N: usize>(src: &[u8]) -> Option<[u8; N]> {
+            src.get(..N).map(|it| it.try_into().unwrap())
+        }
+        let (header_len, len) = match (self.field, self.big_endian) {
+            (LengthField::U16, true) => (2, fixed(src).map(u16::from_be_bytes).map(u64::from)),
+            (LengthField::U16, false) => (2, fixed(src).map(u16::from_le_bytes).map(u64::from)),
+            (LengthField::U32, true) => (4, fixed(src).map(u32::from_be_bytes).map(u64::from)),
+            (LengthField::U32, false) => (4, fixed(src).map(u32::from_le_bytes).map(u64::from)),
+            (LengthField::U64, true) => (8, fixed(src).map(u64::from_be_bytes)),
+            (LengthField::U64, false) => (8, fixed(src).map(u64::from_le_bytes)),
+            (LengthField::Varint, _) => {
+                let mut len = 0u64;
+                let mut header_len = None;
+                for (ix, byte) in src.iter().enumerate().take(MAX_HEADER_LEN) {
+                    let bits = u64::from(byte & 0x7f);
+                    let shift = 7 * ix as u32;
+                    if (bits << shift) >> shift != bits {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "varint length overflows a u64",
+                        ));
+                    }
+                    len |= bits << shift;
+                    if byte & 0x80 == 0 {
+                        header_len = Some(ix + 1);
+                        break;
+                    }
+                }
+                match header_len {
+                    Some(it) => (it, Some(len)),
+                    None if src.len() >= MAX_HEADER_LEN => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "varint length is too long",
+                        ))
+                    }
+                    None => (0, None),
+                }
+            }
+        };
+        match len {
+            Some(len) => Ok(Some((header_len, self.check(len)?))),
+            None => Ok(None),
+        }
+    }
+}
+
+/// The longest header, a varint [`u64`].
+const MAX_HEADER_LEN: usize = 10;
+
+/// Bytes written before an item.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct Header {
+    buf: [u8; MAX_HEADER_LEN],
+    len: u8,
+}
+
+impl Header {
+    fn push(&mut self, bytes: &[u8]) {
+        let start = usize::from(self.len);
+        self.buf[start..start + bytes.len()].copy_from_slice(bytes);
+        self.len += bytes.len() as u8;
+    }
+}
+
+impl AsRef<[u8]> for Header {
+    fn as_ref(&self) -> &[u8] {
+        &self.buf[..usize::from(self.len)]
+    }
+}
+
+pin_project! {
+    /// A [`Stream`] of frames written with [`LengthDelimited`] framing.
+    ///
+    /// See [`IntoStreamExt::into_length_delimited_stream`](crate::IntoStreamExt::into_length_delimited_stream).
+    #[derive(Debug)]
+    pub struct LengthDelimitedStream<R> {
+        #[pin]
+        reader: R,
+        codec: LengthDelimited,
+        buffer: Vec<u8>,
+        // Where the next frame starts in `buffer`.
+        start: usize,
+        eof: bool,
+    }
+}
+
+impl<R> LengthDelimitedStream<R> {
+    pub(crate) fn new(reader: R, codec: LengthDelimited) -> Self {
+        Self {
+            reader,
+            codec,
+            buffer: Vec::new(),
+            start: 0,
+            eof: false,
+        }
+    }
+    /// Get a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+    /// Get a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+    /// Get a pinned mutable reference to the underlying reader.
+    pub fn get_pin_mut(self: Pin<&mut Self>) -> Pin<&mut R> {
+        self.project().reader
+    }
+    /// Consume this stream, returning the underlying reader.
+    ///
+    /// Any bytes which have been read, but not yet returned as a frame, are lost.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+/// How many bytes to read at a time, if the frame is shorter.
+const READ_SIZE: usize = 8 * 1024;
+
+impl<R> Stream for LengthDelimitedStream<R>
+where
+    R: AsyncRead,
+{
+    type Item = io::Result<Vec<u8>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        loop {
+            if *this.eof {
+                return Poll::Ready(None);
+            }
+            let src = &this.buffer[*this.start..];
+            let header = this.codec.decode_header(src).inspect_err(|_| {
+                // we can't find the next frame
+                *this.eof = true
+            })?;
+            let want = match header {
+                Some((header_len, len)) if src.len() >= header_len + len => {
+                    let frame = src[header_len..header_len + len].to_vec();
+                    *this.start += header_len + len;
+                    return Poll::Ready(Some(Ok(frame)));
+                }
+                Some((header_len, len)) => header_len + len - src.len(),
+                None => 1,
+            };
+            // drop the frames which have been returned, once per read
+            this.buffer.drain(..*this.start);
+            *this.start = 0;
+            let filled = this.buffer.len();
+            this.buffer.resize(filled + want.max(READ_SIZE), 0);
+            let mut buf = ReadBuf::new(&mut this.buffer[filled..]);
+            let poll = this.reader.as_mut().poll_read(cx, &mut buf);
+            let read = buf.filled().len();
+            // drop the zeroes, even if the read is pending
+            this.buffer.truncate(filled + read);
+            ready!(poll)?;
+            if read == 0 {
+                *this.eof = true;
+                if !this.buffer.is_empty() {
+                    return Poll::Ready(Some(Err(io::ErrorKind::UnexpectedEof.into())));
+                }
+            }
+        }
+    }
+}
+
+impl<R> FusedStream for LengthDelimitedStream<R>
+where
+    R: AsyncRead,
+{
+    fn is_terminated(&self) -> bool {
+        self.eof
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        test_util::{Mock, Stutter},
+        IntoSinkExt as _, IntoStreamExt as _, Unwritten,
+    };
+    use futures::{executor::block_on, SinkExt as _, StreamExt as _, TryStreamExt as _};
+
+    #[test]
+    fn headers() {
+        let codec = LengthDelimited::new();
+        assert_eq!(codec.header(0x0102).unwrap().as_ref(), [0, 0, 1, 2]);
+        assert_eq!(
+            codec.little_endian().header(0x0102).unwrap().as_ref(),
+            [2, 1, 0, 0]
+        );
+        assert_eq!(codec.u16().header(0x0102).unwrap().as_ref(), [1, 2]);
+        assert_eq!(
+            codec.u64().little_endian().header(1).unwrap().as_ref(),
+            [1, 0, 0, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(codec.varint().header(1).unwrap().as_ref(), [1]);
+        assert_eq!(codec.varint().header(300).unwrap().as_ref(), [0xac, 0x02]);
+        assert!(codec.u16().header(0x10000).is_err());
+        assert!(codec.max_frame_length(4).header(5).is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        for codec in [
+            LengthDelimited::new(),
+            LengthDelimited::new().u16().little_endian(),
+            LengthDelimited::new().u64(),
+            LengthDelimited::new().varint(),
+        ] {
+            block_on(async {
+                let frames = [&b"hello"[..], b"", &[0xff; 300], b"world"];
+                let mut v = vec![];
+                let mut sink = (&mut v)
+                    .into_sink_with_capacity(2)
+                    .with_coalescing(16)
+                    .with_length_delimited(codec);
+                for frame in frames {
+                    sink.feed(frame).await.unwrap();
+                }
+                sink.close().await.unwrap();
+                drop(sink);
+                let decoded = v
+                    .as_slice()
+                    .into_length_delimited_stream(codec)
+                    .try_collect::<Vec<_>>()
+                    .await
+                    .unwrap();
+                assert_eq!(decoded, frames);
+            })
+        }
+    }
+
+    #[test]
+    fn too_long() {
+        block_on(async {
+            let codec = LengthDelimited::new().max_frame_length(4);
+            let mut v = vec![];
+            let mut sink = (&mut v).into_sink().with_length_delimited(codec);
+            sink.send("abcd").await.unwrap();
+            let error = sink.send("abcde").await.unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+            drop(sink);
+
+            let mut stream = b"\x00\x00\x00\x05abcde"
+                .as_slice()
+                .into_length_delimited_stream(codec);
+            assert!(stream.next().await.unwrap().is_err());
+        })
+    }
+
+    #[test]
+    fn truncated() {
+        block_on(async {
+            let mut stream = b"\x00\x00\x00\x05abc"
+                .as_slice()
+                .into_length_delimited_stream(LengthDelimited::new());
+            assert_eq!(
+                stream.next().await.unwrap().unwrap_err().kind(),
+                io::ErrorKind::UnexpectedEof
+            );
+            assert!(stream.next().await.is_none());
+        })
+    }
+
+    #[test]
+    fn pending_reads() {
+        block_on(async {
+            let frames = Stutter::new(b"\x00\x00\x00\x02hi\x00\x00\x00\x05hello")
+                .into_length_delimited_stream(LengthDelimited::new())
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap();
+            assert_eq!(frames, [&b"hi"[..], b"hello"]);
+        })
+    }
+
+    #[test]
+    fn unwritten() {
+        for (ok, header, written) in [(1, &b"\x05"[..], 0), (2, b"", 0), (5, b"", 3)] {
+            block_on(async {
+                let mut sink = Mock::new()
+                    .fail_after(ok)
+                    .into_sink()
+                    .with_length_delimited(LengthDelimited::new().u16())
+                    .with_send_error();
+                let (_, unwritten) = sink.send("abcde").await.unwrap_err().into_parts();
+                let unwritten = unwritten.unwrap();
+                assert_eq!(
+                    unwritten,
+                    Unwritten::Item {
+                        item: "abcde",
+                        written,
+                        header: header.to_vec(),
+                        trailer: vec![],
+                    }
+                );
+                assert_eq!(unwritten.remaining(), &b"abcde"[written..]);
+            })
+        }
+    }
+
+    #[test]
+    fn rejected() {
+        block_on(async {
+            let mut sink = Mock::new()
+                .into_sink()
+                .with_length_delimited(LengthDelimited::new().max_frame_length(2))
+                .with_send_error();
+            let (error, unwritten) = sink.send("abc").await.unwrap_err().into_parts();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+            assert_eq!(
+                unwritten,
+                Some(Unwritten::Item {
+                    item: "abc",
+                    written: 0,
+                    header: vec![],
+                    trailer: vec![],
+                })
+            );
+        })
+    }
+}
@@ -19,20 +19,92 @@
 //! Ported from [`futures::io::AsyncWriteExt::into_sink`](https://docs.rs/futures/0.3.28/futures/io/trait.AsyncWriteExt.html#method.into_sink).
 
 use std::{
-    io,
+    collections::VecDeque,
+    error::Error,
+    fmt,
+    io::{self, IoSlice},
+    marker::PhantomData,
+    mem,
     pin::Pin,
+    sync::Arc,
     task::{ready, Context, Poll},
+    time::Duration,
 };
 
+mod chunks;
+mod csv;
+mod delimited;
+mod fan_out;
+mod framed;
+mod into_stream;
+mod length_delimited;
+mod rate_limit;
+mod reconnect;
+mod sink_writer;
+mod stats;
+#[cfg(test)]
+mod test_util;
+mod timeout;
+mod timer;
+pub use chunks::{Chunks, Scatter};
+pub use csv::{Csv, CsvEncoder};
+pub use delimited::Delimited;
+pub use fan_out::{FailurePolicy, FanOut};
+pub use framed::{Encoder, FramedSink};
+pub use into_stream::{IntoStream, IntoStreamExt};
+pub use length_delimited::{LengthDelimited, LengthDelimitedStream, LengthField};
+pub use rate_limit::RateLimit;
+pub use reconnect::{Reconnect, ReconnectingSink, Resend};
+pub use sink_writer::SinkWriter;
+pub use stats::Stats;
+pub use timeout::Timeouts;
+
 use futures_sink::Sink;
+use length_delimited::Header;
 use pin_project_lite::pin_project;
-use tokio::io::AsyncWrite;
+use rate_limit::Throttle;
+use stats::Recorder;
+use timeout::Deadlines;
+use timer::Timer;
+use tokio::{io::AsyncWrite, time::Instant};
 
 pub trait IntoSinkExt: AsyncWrite {
     /// See the [module documentation](mod@self).
     fn into_sink<Item>(self) -> IntoSink<Self, Item>
     where
         Self: Sized;
+    /// Like [`into_sink`](IntoSinkExt::into_sink), but accept up to `capacity` items
+    /// before [`Sink::poll_ready`] waits on the writer.
+    ///
+    /// Queued items are written in order as the sink is readied, flushed or closed.
+    ///
+    /// # Panics
+    /// - If `capacity` is zero.
+    fn into_sink_with_capacity<Item>(self, capacity: usize) -> IntoSink<Self, Item>
+    where
+        Self: Sized;
+    /// Like [`into_sink`](IntoSinkExt::into_sink), but accept items until at least `high` bytes
+    /// are waiting to be written, rather than waiting on the writer for every item.
+    ///
+    /// Once the high-water mark is reached, [`Sink::poll_ready`] waits on the writer until at most
+    /// `low` bytes remain unwritten.
+    ///
+    /// # Panics
+    /// - If `high` is zero.
+    /// - If `low` is not less than `high`.
+    fn into_sink_with_byte_limit<Item>(self, high: usize, low: usize) -> IntoSink<Self, Item>
+    where
+        Self: Sized;
+    /// Use this writer as a [`Sink`] of messages, which are converted to bytes by `encoder`.
+    ///
+    /// See [`FramedSink::new`] to configure the underlying [`IntoSink`].
+    fn into_framed_sink<En>(self, encoder: En) -> FramedSink<Self, En>
+    where
+        Self: Sized;
+    /// Use this writer as a [`Sink`] of [`Csv`] rows.
+    fn into_csv_sink(self, csv: Csv) -> FramedSink<Self, CsvEncoder>
+    where
+        Self: Sized;
 }
 
 impl<W> IntoSinkExt for W
@@ -43,10 +115,68 @@ where
     where
         Self: Sized,
     {
+        self.into_sink_with_capacity(1)
+    }
+    fn into_sink_with_capacity<Item>(self, capacity: usize) -> IntoSink<Self, Item>
+    where
+        Self: Sized,
+    {
+        assert!(capacity > 0, "capacity must be non-zero");
         IntoSink {
-            writer: self,
-            buffer: None,
+            buffer: VecDeque::with_capacity(capacity),
+            capacity,
+            ..IntoSink::new(self)
+        }
+    }
+    fn into_sink_with_byte_limit<Item>(self, high: usize, low: usize) -> IntoSink<Self, Item>
+    where
+        Self: Sized,
+    {
+        assert!(high > 0, "high-water mark must be non-zero");
+        assert!(
+            low < high,
+            "low-water mark must be less than the high-water mark"
+        );
+        IntoSink {
+            byte_limit: Some(ByteLimit {
+                high,
+                low,
+                draining: false,
+            }),
+            ..IntoSink::new(self)
+        }
+    }
+    fn into_framed_sink<En>(self, encoder: En) -> FramedSink<Self, En>
+    where
+        Self: Sized,
+    {
+        FramedSink::new(self.into_sink(), encoder)
+    }
+    fn into_csv_sink(self, csv: Csv) -> FramedSink<Self, CsvEncoder>
+    where
+        Self: Sized,
+    {
+        self.into_framed_sink(CsvEncoder::new(csv))
+    }
+}
+
+/// Hysteresis for [`IntoSinkExt::into_sink_with_byte_limit`].
+#[derive(Debug)]
+struct ByteLimit {
+    high: usize,
+    low: usize,
+    /// Whether we have hit the high-water mark, and not yet returned to the low-water mark.
+    draining: bool,
+}
+
+impl ByteLimit {
+    fn is_full(&mut self, unwritten: usize) -> bool {
+        if unwritten >= self.high {
+            self.draining = true
+        } else if unwritten <= self.low {
+            self.draining = false
         }
+        self.draining
     }
 }
 
@@ -56,81 +186,994 @@ struct Cursor<T> {
     inner: T,
 }
 
+/// A block of bytes queued for writing.
+#[derive(Debug)]
+enum Entry<Item> {
+    Item {
+        header: Header,
+        item: Item,
+        trailer: Option<Arc<[u8]>>,
+    },
+    /// Small items copied together by [`IntoSink::with_coalescing`].
+    Coalesced(Vec<u8>),
+}
+
+impl<Item> Chunks for Entry<Item>
+where
+    Item: Chunks,
+{
+    fn total_len(&self) -> usize {
+        match self {
+            Entry::Item {
+                header,
+                item,
+                trailer,
+            } => {
+                header.as_ref().len() + item.total_len() + trailer.as_deref().map_or(0, <[u8]>::len)
+            }
+            Entry::Coalesced(it) => it.len(),
+        }
+    }
+    fn chunks_vectored<'a>(&'a self, offset: usize, dst: &mut [IoSlice<'a>]) -> usize {
+        let (header, item, trailer) = match self {
+            Entry::Item {
+                header,
+                item,
+                trailer,
+            } => (
+                header.as_ref(),
+                item,
+                trailer.as_deref().unwrap_or_default(),
+            ),
+            Entry::Coalesced(it) => return it.chunks_vectored(offset, dst),
+        };
+        let mut filled = 0;
+        let offset = match offset.checked_sub(header.len()) {
+            Some(it) => it,
+            None => {
+                let Some(first) = dst.first_mut() else {
+                    return 0;
+                };
+                *first = IoSlice::new(&header[offset..]);
+                filled += 1;
+                0
+            }
+        };
+        filled += item.chunks_vectored(offset, &mut dst[filled..]);
+        // `item` stops early only if `dst` is full
+        let offset = offset.saturating_sub(item.total_len());
+        if let Some(slot) = dst.get_mut(filled) {
+            if offset < trailer.len() {
+                *slot = IoSlice::new(&trailer[offset..]);
+                filled += 1;
+            }
+        }
+        filled
+    }
+}
+
+/// Part of an [`IntoSink`]'s buffer which has not been fully written.
+///
+/// See [`IntoSink::into_parts`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Unwritten<Item> {
+    /// An item, of which the first `written` bytes have been written.
+    ///
+    /// `header` and `trailer` are the parts of any [length prefix](IntoSink::with_length_delimited)
+    /// or [delimiter](IntoSink::with_delimiter) which have not been written.
+    Item {
+        item: Item,
+        written: usize,
+        header: Vec<u8>,
+        trailer: Vec<u8>,
+    },
+    /// Items which were [coalesced](IntoSink::with_coalescing) together,
+    /// of which the first `written` bytes have been written.
+    Coalesced { bytes: Vec<u8>, written: usize },
+}
+
+impl<Item> Unwritten<Item>
+where
+    Item: AsRef<[u8]>,
+{
+    /// The bytes of the item which have not yet been written,
+    /// not including any unwritten `header` or `trailer`.
+    pub fn remaining(&self) -> &[u8] {
+        match self {
+            Unwritten::Item { item, written, .. } => &item.as_ref()[*written..],
+            Unwritten::Coalesced { bytes, written } => &bytes[*written..],
+        }
+    }
+}
+
+impl<Item> From<Cursor<Entry<Item>>> for Unwritten<Item>
+where
+    Item: Chunks,
+{
+    fn from(value: Cursor<Entry<Item>>) -> Self {
+        let Cursor { offset, inner } = value;
+        match inner {
+            Entry::Item {
+                header,
+                item,
+                trailer,
+            } => {
+                let (header, trailer) = (header.as_ref(), trailer.as_deref().unwrap_or_default());
+                let len = item.total_len();
+                Unwritten::Item {
+                    written: offset.saturating_sub(header.len()).min(len),
+                    header: header[offset.min(header.len())..].to_vec(),
+                    trailer: trailer[offset.saturating_sub(header.len() + len)..].to_vec(),
+                    item,
+                }
+            }
+            Entry::Coalesced(bytes) => Unwritten::Coalesced {
+                bytes,
+                written: offset,
+            },
+        }
+    }
+}
+
+/// Errors which may be returned by an [`IntoSink`].
+pub trait IntoSinkError<Item>: From<io::Error> {
+    /// Called when writing fails, or when [`Sink::start_send`] rejects an item.
+    ///
+    /// `take` removes the part of the buffer being written from the sink,
+    /// if it is not called, that part will be retried on the next write.
+    /// For a rejected item, `take` returns the item, which is dropped if it is not called.
+    fn from_write_error(error: io::Error, take: impl FnOnce() -> Unwritten<Item>) -> Self;
+}
+
+/// Keeps the failed item in the sink.
+impl<Item> IntoSinkError<Item> for io::Error {
+    fn from_write_error(error: io::Error, _: impl FnOnce() -> Unwritten<Item>) -> Self {
+        error
+    }
+}
+
+/// An error which hands back the part of the buffer that failed to be written.
+///
+/// See [`IntoSink::with_send_error`].
+#[derive(Debug)]
+pub struct SendError<Item> {
+    error: io::Error,
+    unwritten: Option<Unwritten<Item>>,
+}
+
+impl<Item> SendError<Item> {
+    /// The underlying error.
+    pub fn io_error(&self) -> &io::Error {
+        &self.error
+    }
+    /// The part of the buffer that failed to be written, if this error occurred while writing,
+    /// or the item which [`Sink::start_send`] rejected, with nothing written.
+    ///
+    /// If multiple items were being written with [`IntoSink::with_vectored`], this is the first
+    /// of them - the rest remain in the sink.
+    pub fn unwritten(&self) -> Option<&Unwritten<Item>> {
+        self.unwritten.as_ref()
+    }
+    /// Get the underlying error and the part of the buffer that failed to be written.
+    pub fn into_parts(self) -> (io::Error, Option<Unwritten<Item>>) {
+        (self.error, self.unwritten)
+    }
+}
+
+impl<Item> IntoSinkError<Item> for SendError<Item> {
+    fn from_write_error(error: io::Error, take: impl FnOnce() -> Unwritten<Item>) -> Self {
+        Self {
+            error,
+            unwritten: Some(take()),
+        }
+    }
+}
+
+impl<Item> From<io::Error> for SendError<Item> {
+    fn from(error: io::Error) -> Self {
+        Self {
+            error,
+            unwritten: None,
+        }
+    }
+}
+
+impl<Item> From<SendError<Item>> for io::Error {
+    fn from(value: SendError<Item>) -> Self {
+        value.error
+    }
+}
+
+impl<Item> fmt::Display for SendError<Item> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.unwritten {
+            Some(Unwritten::Item { written, .. }) => {
+                write!(f, "error writing item at offset {written}")
+            }
+            Some(Unwritten::Coalesced { written, .. }) => {
+                write!(f, "error writing coalesced items at offset {written}")
+            }
+            None => f.write_str("error writing to sink"),
+        }
+    }
+}
+
+impl<Item> Error for SendError<Item>
+where
+    Item: fmt::Debug,
+{
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Where an [`IntoSink`] or [`FanOut`] is in the [`Sink`] protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// [`Sink::poll_ready`] must succeed before an item is accepted.
+    Open,
+    /// [`Sink::poll_ready`] has succeeded, so there is room for an item.
+    Ready,
+    /// [`Sink::poll_close`] has been called, but has not completed.
+    Closing,
+    /// [`Sink::poll_close`] has completed.
+    Closed,
+}
+
+/// Fail writes which accept nothing.
+///
+/// `buffer` never contains empty blocks, so these would never make progress.
+fn write_zero(poll: Poll<io::Result<usize>>) -> Poll<io::Result<usize>> {
+    match poll {
+        Poll::Ready(Ok(0)) => Poll::Ready(Err(io::ErrorKind::WriteZero.into())),
+        poll => poll,
+    }
+}
+
+/// An error for a caller which has not followed the [`Sink`] protocol.
+fn misuse(message: &'static str) -> io::Error {
+    io::Error::other(message)
+}
+
+/// The header and trailer to write around `item`.
+fn frame(
+    length_delimited: &Option<LengthDelimited>,
+    delimiter: &Option<Delimited>,
+    item: &(impl Chunks + ?Sized),
+) -> io::Result<(Header, Option<Arc<[u8]>>)> {
+    let header = match length_delimited {
+        Some(codec) => codec.header(item.total_len())?,
+        None => Header::default(),
+    };
+    let trailer = match delimiter {
+        Some(delimited) => Some(delimited.trailer(item)?),
+        None => None,
+    };
+    Ok((header, trailer))
+}
+
+/// Remove the front of `buffer`, which must not be empty.
+fn take_front<Item: Chunks>(
+    buffer: &mut VecDeque<Cursor<Entry<Item>>>,
+    unwritten: &mut usize,
+) -> Unwritten<Item> {
+    let cursor = buffer.pop_front().expect("buffer must not be empty");
+    *unwritten -= cursor.inner.total_len() - cursor.offset;
+    cursor.into()
+}
+
+/// Configuration for [`IntoSink::with_coalescing`].
+#[derive(Debug)]
+struct Coalesce {
+    capacity: usize,
+    /// A previously written buffer, kept to save allocations.
+    spare: Vec<u8>,
+}
+
+/// When an [`IntoSink`] should flush its writer without being asked to.
+///
+/// See [`IntoSink::with_flush_policy`].
+/// Explicit calls to [`Sink::poll_flush`] are always honoured.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FlushPolicy {
+    bytes: Option<usize>,
+    items: Option<usize>,
+    idle: Option<Duration>,
+}
+
+impl FlushPolicy {
+    /// A policy which never flushes automatically.
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Flush once at least `bytes` have been accepted since the last flush.
+    pub fn bytes(mut self, bytes: usize) -> Self {
+        self.bytes = Some(bytes);
+        self
+    }
+    /// Flush once at least `items` have been accepted since the last flush.
+    pub fn items(mut self, items: usize) -> Self {
+        self.items = Some(items);
+        self
+    }
+    /// Flush once `idle` has elapsed since an item was last accepted.
+    ///
+    /// [`Sink::poll_ready`] flushes an idle sink before accepting the next item.
+    /// To flush as soon as `idle` has elapsed, even if no more items are sent, also drive
+    /// [`IntoSink::poll_idle_flush`].
+    ///
+    /// This uses [`tokio::time`], so must be used within a runtime with time enabled.
+    pub fn idle(mut self, idle: Duration) -> Self {
+        self.idle = Some(idle);
+        self
+    }
+}
+
+/// State for [`IntoSink::with_flush_policy`].
+#[derive(Debug)]
+struct AutoFlush {
+    policy: FlushPolicy,
+    /// Bytes accepted since the last flush.
+    bytes: usize,
+    /// Items accepted since the last flush.
+    items: usize,
+    idle: Timer,
+}
+
+impl AutoFlush {
+    fn record(&mut self, len: usize) {
+        self.bytes += len;
+        self.items += 1;
+        if let Some(idle) = self.policy.idle {
+            self.idle.reset(Instant::now() + idle)
+        }
+    }
+    fn is_due(&mut self, cx: &mut Context<'_>) -> bool {
+        if self.items == 0 {
+            return false;
+        }
+        matches!(self.policy.bytes, Some(it) if self.bytes >= it)
+            || matches!(self.policy.items, Some(it) if self.items >= it)
+            || self.is_idle(cx)
+    }
+    /// Whether items have been accepted since the last flush, and the idle deadline has passed.
+    fn is_idle(&mut self, cx: &mut Context<'_>) -> bool {
+        self.items > 0 && self.idle.poll(cx).is_ready()
+    }
+    fn reset(&mut self) {
+        self.bytes = 0;
+        self.items = 0;
+    }
+}
+
 pin_project! {
     /// See the [module documentation](mod@self).
     #[derive(Debug)]
-    pub struct IntoSink<W, Item> {
+    pub struct IntoSink<W, Item, E = io::Error> {
         #[pin]
         writer: W,
-        buffer: Option<Cursor<Item>>,
+        buffer: VecDeque<Cursor<Entry<Item>>>,
+        capacity: usize,
+        vectored: bool,
+        // The sum of the unwritten lengths in `buffer`.
+        unwritten: usize,
+        byte_limit: Option<ByteLimit>,
+        coalesce: Option<Coalesce>,
+        length_delimited: Option<LengthDelimited>,
+        delimiter: Option<Delimited>,
+        auto_flush: Option<AutoFlush>,
+        throttle: Option<Throttle>,
+        deadlines: Option<Deadlines>,
+        stats: Option<Recorder>,
+        state: State,
+        error: PhantomData<fn() -> E>,
+    }
+}
+
+/// The maximum number of buffers passed to a single [`AsyncWrite::poll_write_vectored`] call.
+const MAX_IO_SLICES: usize = 64;
+
+impl<W, Item, E> IntoSink<W, Item, E> {
+    fn new(writer: W) -> Self {
+        Self {
+            writer,
+            buffer: VecDeque::new(),
+            capacity: usize::MAX,
+            vectored: false,
+            unwritten: 0,
+            byte_limit: None,
+            coalesce: None,
+            length_delimited: None,
+            delimiter: None,
+            auto_flush: None,
+            throttle: None,
+            deadlines: None,
+            stats: None,
+            state: State::Open,
+            error: PhantomData,
+        }
+    }
+    /// Write several queued items at once if the writer
+    /// [supports vectored writes](AsyncWrite::is_write_vectored).
+    ///
+    /// How many items may be queued is set by [`into_sink_with_capacity`](IntoSinkExt::into_sink_with_capacity)
+    /// or [`into_sink_with_byte_limit`](IntoSinkExt::into_sink_with_byte_limit).
+    ///
+    /// Without this, such writers are still passed each item's chunks, along with any
+    /// [length prefix](Self::with_length_delimited) or [delimiter](Self::with_delimiter),
+    /// in a single [`AsyncWrite::poll_write_vectored`] call.
+    pub fn with_vectored(mut self) -> Self {
+        self.vectored = true;
+        self
+    }
+    /// Copy items shorter than `capacity` bytes into a shared buffer of that size, so that they
+    /// may be written together.
+    ///
+    /// Larger items are written directly, after any buffered bytes.
+    /// A buffer which still has room does not count towards the sink's [`capacity`](Self::capacity).
+    ///
+    /// # Panics
+    /// - If `capacity` is zero.
+    pub fn with_coalescing(mut self, capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be non-zero");
+        self.coalesce = Some(Coalesce {
+            capacity,
+            spare: Vec::new(),
+        });
+        self
+    }
+    /// Prefix each item with its length, according to `codec`.
+    ///
+    /// Items which are too long for `codec` are rejected by [`Sink::start_send`] with
+    /// [`io::ErrorKind::InvalidData`].
+    /// See [`IntoStreamExt::into_length_delimited_stream`] for reading the frames back.
+    pub fn with_length_delimited(mut self, codec: LengthDelimited) -> Self {
+        self.length_delimited = Some(codec);
+        self
+    }
+    /// Follow each item with a delimiter, such as a newline.
+    ///
+    /// The delimiter is written after the item without copying them together.
+    pub fn with_delimiter(mut self, delimited: Delimited) -> Self {
+        self.delimiter = Some(delimited);
+        self
+    }
+    /// Flush the writer automatically according to `policy`, when the sink is readied.
+    pub fn with_flush_policy(mut self, policy: FlushPolicy) -> Self {
+        self.auto_flush = Some(AutoFlush {
+            policy,
+            bytes: 0,
+            items: 0,
+            idle: Timer::default(),
+        });
+        self
+    }
+    /// Delay writes so that they stay within `limit`, splitting items as required.
+    ///
+    /// This uses [`tokio::time`], so must be used within a runtime with time enabled.
+    pub fn with_rate_limit(mut self, limit: RateLimit) -> Self {
+        self.throttle = Some(Throttle::new(limit));
+        self
+    }
+    /// Fail with [`io::ErrorKind::TimedOut`] if the writer takes longer than `timeouts` allow.
+    ///
+    /// This uses [`tokio::time`], so must be used within a runtime with time enabled.
+    pub fn with_timeouts(mut self, timeouts: Timeouts) -> Self {
+        self.deadlines = Some(Deadlines::new(timeouts));
+        self
+    }
+    /// Return [`SendError`]s from this sink, which hand back the item that failed to be written.
+    pub fn with_send_error(self) -> IntoSink<W, Item, SendError<Item>> {
+        let Self {
+            writer,
+            buffer,
+            capacity,
+            vectored,
+            unwritten,
+            byte_limit,
+            coalesce,
+            length_delimited,
+            delimiter,
+            auto_flush,
+            throttle,
+            deadlines,
+            stats,
+            state,
+            error: _,
+        } = self;
+        IntoSink {
+            writer,
+            buffer,
+            capacity,
+            vectored,
+            unwritten,
+            byte_limit,
+            coalesce,
+            length_delimited,
+            delimiter,
+            auto_flush,
+            throttle,
+            deadlines,
+            stats,
+            state,
+            error: PhantomData,
+        }
+    }
+    /// Record [`Stats`] for this sink, see [`stats`](Self::stats).
+    pub fn with_stats(mut self) -> Self {
+        self.stats = Some(Recorder::default());
+        self
+    }
+    /// A snapshot of this sink's [`Stats`], if they are being [recorded](Self::with_stats).
+    pub fn stats(&self) -> Option<Stats> {
+        self.stats.as_ref().map(Recorder::snapshot)
+    }
+    /// Get a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+    /// Get a mutable reference to the underlying writer.
+    ///
+    /// Care should be taken not to write to the writer directly while items are outstanding.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+    /// Get a pinned mutable reference to the underlying writer.
+    ///
+    /// Care should be taken not to write to the writer directly while items are outstanding.
+    pub fn get_pin_mut(self: Pin<&mut Self>) -> Pin<&mut W> {
+        self.project().writer
+    }
+    /// Consume this sink, returning the underlying writer.
+    ///
+    /// Any items which have not been written are lost, see [`into_parts`](Self::into_parts).
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+    /// Consume this sink, returning the underlying writer, and any items which have not been
+    /// fully written, in the order they were accepted.
+    pub fn into_parts(self) -> (W, Vec<Unwritten<Item>>)
+    where
+        Item: Chunks,
+    {
+        (
+            self.writer,
+            self.buffer.into_iter().map(Into::into).collect(),
+        )
+    }
+    /// The number of items which may be queued before [`Sink::poll_ready`] waits on the writer.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+    /// The number of items which have been accepted, but not yet fully written.
+    ///
+    /// Items which have been [coalesced](Self::with_coalescing) together count as one.
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+    /// The number of entries in `buffer`, not counting a coalescing buffer which may accept more
+    /// items.
+    fn pending(&self) -> usize {
+        match (&self.coalesce, self.buffer.back()) {
+            (
+                Some(Coalesce { capacity, .. }),
+                Some(Cursor {
+                    inner: Entry::Coalesced(buf),
+                    ..
+                }),
+            ) if buf.len() < *capacity => self.buffer.len() - 1,
+            _ => self.buffer.len(),
+        }
+    }
+    /// Whether all accepted items have been written.
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+    /// The number of bytes which have been accepted, but not yet written.
+    pub fn unwritten_bytes(&self) -> usize {
+        self.unwritten
+    }
+    /// Fail unless [`Sink::start_send`] may be called.
+    pub(crate) fn check_ready(&self) -> io::Result<()> {
+        match self.state {
+            State::Ready => Ok(()),
+            State::Open => Err(misuse("start_send called without poll_ready")),
+            State::Closing | State::Closed => Err(misuse("start_send called after poll_close")),
+        }
+    }
+    /// Whether [`Sink::poll_close`] has been called.
+    fn is_closing(&self) -> bool {
+        matches!(self.state, State::Closing | State::Closed)
+    }
+    /// Write the rest of the buffer to `writer` instead, starting the front item again if `rewind`.
+    fn replace_writer(self: Pin<&mut Self>, writer: W, rewind: bool) {
+        let mut this = self.project();
+        this.writer.set(writer);
+        if let (true, Some(front)) = (rewind, this.buffer.front_mut()) {
+            *this.unwritten += front.offset;
+            front.offset = 0;
+        }
     }
 }
 
-impl<W, Item> IntoSink<W, Item>
+impl<W, Item, E> IntoSink<W, Item, E>
 where
     W: AsyncWrite,
-    Item: AsRef<[u8]>,
+    Item: Chunks,
+    E: IntoSinkError<Item>,
 {
-    /// If we have an outstanding block in `buffer` attempt to push it into the writer, does _not_
-    /// flush the writer after it succeeds in pushing the block into it.
-    fn poll_flush_buffer(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
-        let mut this = self.project();
-        if let Some(cursor) = this.buffer {
-            loop {
-                let bytes = cursor.inner.as_ref();
-                let written = ready!(this.writer.as_mut().poll_write(cx, &bytes[cursor.offset..]))?;
-                cursor.offset += written;
-                if cursor.offset == bytes.len() {
+    fn is_full(mut self: Pin<&mut Self>) -> bool {
+        let pending = self.pending();
+        let this = self.as_mut().project();
+        let over_bytes = match this.byte_limit {
+            Some(limit) => limit.is_full(*this.unwritten),
+            None => false,
+        };
+        over_bytes || pending >= *this.capacity
+    }
+
+    /// Make a single write of (the front of) `buffer` into the writer, which must not be empty,
+    /// failing if the front item has timed out.
+    fn poll_write_buffer(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), E>> {
+        let len = self.buffer.len();
+        let poll = self.as_mut().poll_write_once(cx);
+        let this = self.project();
+        let Some(Deadlines {
+            item: Some(deadline),
+            ..
+        }) = this.deadlines
+        else {
+            return poll;
+        };
+        match poll {
+            // waiting on the rate limit rather than the writer
+            Poll::Pending if this.throttle.as_ref().is_some_and(Throttle::is_waiting) => poll,
+            Poll::Pending if deadline.elapsed(cx) => {
+                stats::record(this.stats, Recorder::timed_out);
+                Poll::Ready(Err(E::from_write_error(
+                    timeout::timed_out("item was not written in time"),
+                    || take_front(this.buffer, this.unwritten),
+                )))
+            }
+            // the front item has only been partly written
+            Poll::Ready(Ok(())) if this.buffer.len() == len => poll,
+            Poll::Pending => poll,
+            Poll::Ready(_) => {
+                deadline.reset();
+                poll
+            }
+        }
+    }
+
+    /// Make a single write of (the front of) `buffer` into the writer, which must not be empty.
+    fn poll_write_once(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), E>> {
+        let this = self.project();
+        let buffer = this.buffer;
+        let (offered, res) = if this.writer.is_write_vectored() {
+            // only `with_vectored` writes more than one entry at a time
+            let entries = if *this.vectored { buffer.len() } else { 1 };
+            let mut slices = [IoSlice::new(&[]); MAX_IO_SLICES];
+            let mut len = 0;
+            for cursor in buffer.iter().take(entries) {
+                if len == MAX_IO_SLICES {
                     break;
                 }
+                len += cursor
+                    .inner
+                    .chunks_vectored(cursor.offset, &mut slices[len..]);
+            }
+            let last;
+            if let Some(throttle) = this.throttle.as_mut() {
+                let offered = slices[..len].iter().map(|it| it.len()).sum();
+                let allowed = ready!(throttle.poll_acquire(cx, offered));
+                let last_len;
+                (len, last_len) = rate_limit::limit(&slices[..len], allowed);
+                last = slices[len - 1];
+                slices[len - 1] = IoSlice::new(&last[..last_len]);
+            }
+            let slices = &slices[..len];
+            let poll = write_zero(this.writer.poll_write_vectored(cx, slices));
+            (
+                slices.iter().map(|it| it.len()).sum(),
+                ready!(stats::poll(this.stats, poll)),
+            )
+        } else {
+            let cursor = buffer.front().expect("buffer must not be empty");
+            let mut slice = [IoSlice::new(&[])];
+            cursor.inner.chunks_vectored(cursor.offset, &mut slice);
+            let whole;
+            if let Some(throttle) = this.throttle.as_mut() {
+                let allowed = ready!(throttle.poll_acquire(cx, slice[0].len()));
+                whole = slice[0];
+                slice[0] = IoSlice::new(&whole[..allowed.min(whole.len())]);
+            }
+            let poll = write_zero(this.writer.poll_write(cx, &slice[0]));
+            (slice[0].len(), ready!(stats::poll(this.stats, poll)))
+        };
+        stats::record(this.stats, |it| it.write(offered, &res));
+        if let (Some(throttle), Ok(written)) = (this.throttle.as_mut(), &res) {
+            throttle.consume(*written)
+        }
+        let unwritten = this.unwritten;
+        let mut written = match res {
+            Ok(it) => it,
+            Err(e) => {
+                return Poll::Ready(Err(E::from_write_error(e, || {
+                    take_front(buffer, unwritten)
+                })))
+            }
+        };
+        *unwritten -= written;
+        while let Some(cursor) = buffer.front_mut() {
+            let remaining = cursor.inner.total_len() - cursor.offset;
+            if written < remaining {
+                cursor.offset += written;
+                break;
             }
+            written -= remaining;
+            let done = buffer.pop_front().map(|it| it.inner);
+            if let (Some(Entry::Coalesced(mut buf)), Some(coalesce)) =
+                (done, this.coalesce.as_mut())
+            {
+                buf.clear();
+                coalesce.spare = buf;
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    /// If we have outstanding blocks in `buffer` attempt to push them into the writer, does _not_
+    /// flush the writer after it succeeds in pushing the blocks into it.
+    fn poll_flush_buffer(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), E>> {
+        while !self.buffer.is_empty() {
+            ready!(self.as_mut().poll_write_buffer(cx))?;
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    /// Push all outstanding blocks into the writer, and flush it.
+    fn poll_flush_all(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), E>> {
+        ready!(self.as_mut().poll_flush_buffer(cx))?;
+        let this = self.project();
+        ready!(stats::poll(this.stats, this.writer.poll_flush(cx)))?;
+        stats::record(this.stats, Recorder::flush);
+        if let Some(auto_flush) = this.auto_flush {
+            auto_flush.reset()
         }
-        *this.buffer = None;
         Poll::Ready(Ok(()))
     }
+    /// Queue `item`, converting it to an `Item` only if it is not coalesced.
+    fn push<T: Chunks>(
+        self: Pin<&mut Self>,
+        item: T,
+        into_item: impl FnOnce(T) -> Item,
+    ) -> Result<(), E> {
+        let framing = self.check_ready();
+        let this = self.project();
+        let framing = framing.and_then(|()| {
+            *this.state = State::Open;
+            frame(this.length_delimited, this.delimiter, &item)
+        });
+        let (header, trailer) = match framing {
+            Ok(it) => it,
+            // hand the rejected item back
+            Err(e) => {
+                return Err(E::from_write_error(e, || Unwritten::Item {
+                    item: into_item(item),
+                    written: 0,
+                    header: vec![],
+                    trailer: vec![],
+                }))
+            }
+        };
+        let len =
+            header.as_ref().len() + item.total_len() + trailer.as_deref().map_or(0, <[u8]>::len);
+        *this.unwritten += len;
+        if let Some(auto_flush) = this.auto_flush {
+            auto_flush.record(len)
+        }
+        stats::record(this.stats, Recorder::item);
+        if len == 0 {
+            return Ok(());
+        }
+        match this.coalesce {
+            Some(coalesce) if len < coalesce.capacity => match this.buffer.back_mut() {
+                Some(Cursor {
+                    inner: Entry::Coalesced(buf),
+                    ..
+                }) if buf.len() + len <= coalesce.capacity => {
+                    buf.extend_from_slice(header.as_ref());
+                    chunks::extend(buf, &item);
+                    buf.extend_from_slice(trailer.as_deref().unwrap_or_default());
+                }
+                _ => {
+                    let mut buf = mem::take(&mut coalesce.spare);
+                    buf.reserve(coalesce.capacity);
+                    buf.extend_from_slice(header.as_ref());
+                    chunks::extend(&mut buf, &item);
+                    buf.extend_from_slice(trailer.as_deref().unwrap_or_default());
+                    this.buffer.push_back(Cursor {
+                        offset: 0,
+                        inner: Entry::Coalesced(buf),
+                    })
+                }
+            },
+            _ => this.buffer.push_back(Cursor {
+                offset: 0,
+                inner: Entry::Item {
+                    header,
+                    item: into_item(item),
+                    trailer,
+                },
+            }),
+        }
+        Ok(())
+    }
 }
 
-impl<W, Item> Sink<Item> for IntoSink<W, Item>
+impl<W, E> IntoSink<W, Vec<u8>, E>
 where
     W: AsyncWrite,
-    Item: AsRef<[u8]>,
+    E: IntoSinkError<Vec<u8>>,
 {
-    type Error = io::Error;
+    /// Like [`Sink::start_send`], but copy `bytes` into the [coalescing](Self::with_coalescing)
+    /// buffer if they fit, and only allocate an item otherwise.
+    pub(crate) fn start_send_slice(self: Pin<&mut Self>, bytes: &[u8]) -> Result<(), E> {
+        self.push(bytes, <[u8]>::to_vec)
+    }
+}
 
-    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        ready!(self.poll_flush_buffer(cx))?;
+impl<W, Item, E> Sink<Item> for IntoSink<W, Item, E>
+where
+    W: AsyncWrite,
+    Item: Chunks,
+    E: IntoSinkError<Item>,
+{
+    type Error = E;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        if let State::Closing | State::Closed = self.state {
+            return Poll::Ready(Err(misuse("poll_ready called after poll_close").into()));
+        }
+        if let Some(auto_flush) = self.as_mut().project().auto_flush {
+            if auto_flush.is_due(cx) {
+                ready!(self.as_mut().poll_flush_all(cx))?;
+            }
+        }
+        while self.as_mut().is_full() {
+            ready!(self.as_mut().poll_write_buffer(cx))?;
+        }
+        *self.project().state = State::Ready;
         Poll::Ready(Ok(()))
     }
 
     fn start_send(self: Pin<&mut Self>, item: Item) -> Result<(), Self::Error> {
-        debug_assert!(self.buffer.is_none());
-        *self.project().buffer = Some(Cursor {
-            offset: 0,
-            inner: item,
-        });
-        Ok(())
+        self.push(item, |it| it)
     }
 
     fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        ready!(self.as_mut().poll_flush_buffer(cx))?;
-        ready!(self.project().writer.poll_flush(cx))?;
-        Poll::Ready(Ok(()))
+        let poll = self.as_mut().poll_flush_all(cx);
+        let this = self.project();
+        if let Some(Deadlines {
+            flush: Some(deadline),
+            ..
+        }) = this.deadlines
+        {
+            if deadline.check(cx, &poll) {
+                stats::record(this.stats, Recorder::timed_out);
+                return Poll::Ready(Err(timeout::timed_out("flush timed out").into()));
+            }
+        }
+        poll
     }
 
     fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let poll = self.as_mut().poll_close_all(cx);
+        let this = self.project();
+        if let Some(Deadlines {
+            close: Some(deadline),
+            ..
+        }) = this.deadlines
+        {
+            if deadline.check(cx, &poll) {
+                stats::record(this.stats, Recorder::timed_out);
+                return Poll::Ready(Err(timeout::timed_out("close timed out").into()));
+            }
+        }
+        poll
+    }
+}
+
+impl<W, Item, E> IntoSink<W, Item, E>
+where
+    W: AsyncWrite,
+    Item: Chunks,
+    E: IntoSinkError<Item>,
+{
+    /// Push all outstanding blocks into the writer, and shut it down.
+    fn poll_close_all(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), E>> {
+        match self.state {
+            State::Closed => return Poll::Ready(Ok(())),
+            _ => *self.as_mut().project().state = State::Closing,
+        }
         ready!(self.as_mut().poll_flush_buffer(cx))?;
-        ready!(self.project().writer.poll_shutdown(cx))?;
+        let this = self.project();
+        ready!(stats::poll(this.stats, this.writer.poll_shutdown(cx)))?;
+        *this.state = State::Closed;
         Poll::Ready(Ok(()))
     }
+    /// Wait until the [idle deadline](FlushPolicy::idle) has passed, and then flush the sink.
+    ///
+    /// Never completes if there is no idle deadline, or nothing has been accepted since the sink
+    /// was last flushed.
+    /// Poll this alongside the source of items, so that an idle sink is flushed without waiting
+    /// for another item.
+    pub fn poll_idle_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), E>> {
+        let idle = match self.as_mut().project().auto_flush {
+            Some(auto_flush) => auto_flush.is_idle(cx),
+            None => false,
+        };
+        match idle {
+            true => self.poll_flush(cx),
+            false => Poll::Pending,
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    use futures::{executor::block_on, stream, StreamExt as _};
+    use crate::test_util::{paused, Mock};
+    use futures::{executor::block_on, future, stream, SinkExt as _, StreamExt as _};
     use std::io;
 
+    /// A writer which responds to each call to [`AsyncWrite::poll_write`] with the next step of
+    /// a script.
+    ///
+    /// `Ok(n)` steps accept at most `n` bytes.
+    #[derive(Debug, Default)]
+    struct Script {
+        steps: VecDeque<Poll<io::Result<usize>>>,
+        written: Vec<u8>,
+    }
+
+    impl Script {
+        fn new(steps: impl IntoIterator<Item = Poll<io::Result<usize>>>) -> Self {
+            Self {
+                steps: steps.into_iter().collect(),
+                written: vec![],
+            }
+        }
+    }
+
+    impl AsyncWrite for Script {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            match self.steps.pop_front().expect("script has finished") {
+                Poll::Ready(Ok(n)) => {
+                    let n = n.min(buf.len());
+                    self.written.extend_from_slice(&buf[..n]);
+                    Poll::Ready(Ok(n))
+                }
+                Poll::Ready(Err(e)) => Poll::Ready(Err(e)),
+                Poll::Pending => {
+                    cx.waker().wake_by_ref();
+                    Poll::Pending
+                }
+            }
+        }
+        fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+        fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
     #[test]
     fn readme() {
         assert!(
@@ -154,4 +1197,396 @@ mod tests {
             assert_eq!(v, b"helloworld");
         })
     }
+
+    #[test]
+    fn vectored() {
+        block_on(async {
+            let mut writer = Mock::new().limit(7).vectored();
+            let mut sink = (&mut writer).into_sink_with_capacity(4).with_vectored();
+            for item in ["ab", "cde", "f", "ghij", "klm"] {
+                sink.feed(item).await.unwrap();
+            }
+            sink.flush().await.unwrap();
+            assert_eq!(writer.written(), b"abcdefghijklm");
+            // [ab cde f g][hij klm]
+            assert_eq!(writer.calls, 2);
+        })
+    }
+
+    #[test]
+    fn vectored_entry() {
+        block_on(async {
+            let mut writer = Mock::new().vectored();
+            let mut sink = (&mut writer)
+                .into_sink()
+                .with_length_delimited(LengthDelimited::new().u16());
+            sink.send(Scatter(["ab", "cd"])).await.unwrap();
+            sink.send(Scatter(["e", "f"])).await.unwrap();
+            drop(sink);
+            assert_eq!(writer.written(), b"\x00\x04abcd\x00\x02ef");
+            assert_eq!(writer.calls, 2);
+        })
+    }
+
+    #[test]
+    fn capacity() {
+        let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+        let mut sink = Mock::stuck().into_sink_with_capacity(3);
+        for item in ["a", "b", "c"] {
+            assert!(matches!(
+                sink.poll_ready_unpin(&mut cx),
+                Poll::Ready(Ok(()))
+            ));
+            sink.start_send_unpin(item).unwrap();
+        }
+        assert_eq!(sink.len(), 3);
+        assert!(sink.poll_ready_unpin(&mut cx).is_pending());
+        assert!(sink.poll_flush_unpin(&mut cx).is_pending());
+    }
+
+    #[test]
+    fn byte_limit() {
+        let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+        let mut writer = Mock::new().limit(4).vectored();
+        let mut sink = (&mut writer).into_sink_with_byte_limit(10, 2);
+        for item in ["abc", "def", "ghi"] {
+            assert!(matches!(
+                sink.poll_ready_unpin(&mut cx),
+                Poll::Ready(Ok(()))
+            ));
+            sink.start_send_unpin(item).unwrap();
+        }
+        assert_eq!(sink.unwritten_bytes(), 9);
+        assert!(matches!(
+            sink.poll_ready_unpin(&mut cx),
+            Poll::Ready(Ok(()))
+        ));
+        sink.start_send_unpin("jk").unwrap();
+        // over the high-water mark, so write down to the low-water mark
+        assert!(matches!(
+            sink.poll_ready_unpin(&mut cx),
+            Poll::Ready(Ok(()))
+        ));
+        assert_eq!(sink.unwritten_bytes(), 2);
+        drop(sink);
+        assert_eq!(writer.written(), b"abcdefghi");
+        assert_eq!(writer.calls, 3);
+    }
+
+    #[test]
+    fn coalescing() {
+        block_on(async {
+            let mut writer = Mock::new().vectored();
+            let mut sink = (&mut writer).into_sink().with_coalescing(8);
+            for item in ["a", "bc", "def", "gh", "ijklmnopq", "r"] {
+                sink.feed(item).await.unwrap();
+            }
+            sink.close().await.unwrap();
+            assert_eq!(writer.written(), b"abcdefghijklmnopqr");
+            // [a bc def gh][ijklmnopq][r]
+            assert_eq!(writer.calls, 3);
+        })
+    }
+
+    #[test]
+    fn flush_policy_items() {
+        block_on(async {
+            let mut writer = Mock::new().vectored();
+            let mut sink = (&mut writer)
+                .into_sink_with_capacity(8)
+                .with_flush_policy(FlushPolicy::new().items(2));
+            for item in ["a", "b", "c"] {
+                sink.feed(item).await.unwrap();
+            }
+            drop(sink);
+            assert_eq!(writer.written(), b"ab");
+            assert_eq!(writer.flushes, 1);
+        })
+    }
+
+    #[test]
+    fn flush_policy_idle() {
+        paused(async {
+            let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+            let mut writer = Mock::new().vectored();
+            let mut sink = (&mut writer)
+                .into_sink_with_capacity(8)
+                .with_flush_policy(FlushPolicy::new().idle(Duration::from_secs(1)));
+            sink.feed("a").await.unwrap();
+            assert!(sink.poll_ready_unpin(&mut cx).is_ready());
+            assert!(!sink.is_empty());
+            tokio::time::advance(Duration::from_secs(2)).await;
+            assert!(sink.poll_ready_unpin(&mut cx).is_ready());
+            assert!(sink.is_empty());
+            drop(sink);
+            assert_eq!(writer.flushes, 1);
+        })
+    }
+
+    #[test]
+    fn poll_idle_flush() {
+        paused(async {
+            let start = Instant::now();
+            let mut writer = Mock::new().vectored();
+            let mut sink = (&mut writer)
+                .into_sink_with_capacity(8)
+                .with_flush_policy(FlushPolicy::new().idle(Duration::from_secs(1)));
+            sink.feed("a").await.unwrap();
+            sink.feed("b").await.unwrap();
+            future::poll_fn(|cx| Pin::new(&mut sink).poll_idle_flush(cx))
+                .await
+                .unwrap();
+            assert_eq!(start.elapsed(), Duration::from_secs(1));
+            assert!(sink.is_empty());
+            drop(sink);
+            assert_eq!(writer.written(), b"ab");
+            assert_eq!(writer.flushes, 1);
+        })
+    }
+
+    #[test]
+    fn into_parts() {
+        let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+        let writer = Mock::new().limit(4).vectored();
+        let mut sink = writer
+            .into_sink_with_capacity(3)
+            .with_vectored()
+            .with_coalescing(3);
+        for item in ["abc", "de", "fghi"] {
+            assert!(matches!(
+                sink.poll_ready_unpin(&mut cx),
+                Poll::Ready(Ok(()))
+            ));
+            sink.start_send_unpin(item).unwrap();
+        }
+        assert!(matches!(
+            sink.poll_ready_unpin(&mut cx),
+            Poll::Ready(Ok(()))
+        ));
+        let (writer, unwritten) = sink.into_parts();
+        assert_eq!(writer.written(), b"abcd");
+        assert_eq!(
+            unwritten,
+            [
+                Unwritten::Coalesced {
+                    bytes: b"de".to_vec(),
+                    written: 1
+                },
+                Unwritten::Item {
+                    item: "fghi",
+                    written: 0,
+                    header: vec![],
+                    trailer: vec![],
+                }
+            ]
+        );
+        assert_eq!(unwritten[0].remaining(), b"e");
+    }
+
+    #[test]
+    fn send_error() {
+        block_on(async {
+            let mut sink = Mock::new().fail_after(3).into_sink().with_send_error();
+            sink.send("ab").await.unwrap();
+            let (error, unwritten) = sink.send("cde").await.unwrap_err().into_parts();
+            assert_eq!(error.kind(), io::ErrorKind::BrokenPipe);
+            assert_eq!(
+                unwritten,
+                Some(Unwritten::Item {
+                    item: "cde",
+                    written: 1,
+                    header: vec![],
+                    trailer: vec![],
+                })
+            );
+            assert!(sink.is_empty());
+            assert_eq!(sink.unwritten_bytes(), 0);
+        })
+    }
+
+    #[test]
+    fn io_error_keeps_item() {
+        block_on(async {
+            let mut sink = Mock::new().fail_after(1).into_sink();
+            let error = sink.send("ab").await.unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::BrokenPipe);
+            assert_eq!(sink.len(), 1);
+            assert_eq!(sink.unwritten_bytes(), 1);
+        })
+    }
+
+    #[test]
+    fn write_zero() {
+        block_on(async {
+            let mut sink = Script::new([Poll::Ready(Ok(1)), Poll::Ready(Ok(0))])
+                .into_sink()
+                .with_send_error();
+            let (error, unwritten) = sink.send("abc").await.unwrap_err().into_parts();
+            assert_eq!(error.kind(), io::ErrorKind::WriteZero);
+            assert_eq!(
+                unwritten,
+                Some(Unwritten::Item {
+                    item: "abc",
+                    written: 1,
+                    header: vec![],
+                    trailer: vec![],
+                })
+            );
+        })
+    }
+
+    #[test]
+    fn script() {
+        block_on(async {
+            let mut script = Script::new([
+                Poll::Ready(Ok(2)),
+                Poll::Pending,
+                Poll::Ready(Ok(1)),
+                Poll::Ready(Err(io::ErrorKind::Interrupted.into())),
+                Poll::Pending,
+                Poll::Ready(Ok(usize::MAX)),
+                Poll::Ready(Ok(usize::MAX)),
+                Poll::Ready(Ok(0)),
+                Poll::Ready(Ok(usize::MAX)),
+            ]);
+            let mut sink = (&mut script).into_sink();
+            assert_eq!(
+                sink.send("abcd").await.unwrap_err().kind(),
+                io::ErrorKind::Interrupted
+            );
+            // the item is retried from where it left off
+            sink.send("efg").await.unwrap();
+            assert_eq!(
+                sink.send("h").await.unwrap_err().kind(),
+                io::ErrorKind::WriteZero
+            );
+            // the failed item is retried, but empty items are never written
+            sink.send("").await.unwrap();
+            drop(sink);
+            assert_eq!(script.written, b"abcdefgh");
+            assert!(script.steps.is_empty());
+        })
+    }
+
+    #[test]
+    fn protocol() {
+        block_on(async {
+            let mut v = vec![];
+            let mut sink = (&mut v).into_sink_with_capacity(2);
+            assert!(sink.start_send_unpin("a").is_err());
+            sink.send("b").await.unwrap();
+            sink.close().await.unwrap();
+            sink.close().await.unwrap();
+            assert!(sink.start_send_unpin("c").is_err());
+            assert!(sink.send("d").await.is_err());
+            drop(sink);
+            assert_eq!(v, b"b");
+        })
+    }
+
+    #[test]
+    fn scatter() {
+        block_on(async {
+            let mut writer = Mock::new().limit(4).vectored();
+            let mut sink = (&mut writer).into_sink_with_capacity(2).with_vectored();
+            sink.feed(Scatter(vec!["ab", "cde", "f"])).await.unwrap();
+            sink.feed(Scatter(vec!["gh", "ij"])).await.unwrap();
+            sink.flush().await.unwrap();
+            assert_eq!(writer.written(), b"abcdefghij");
+            // [ab cd][e f gh][ij]
+            assert_eq!(writer.calls, 3);
+        })
+    }
+
+    #[test]
+    fn framing() {
+        block_on(async {
+            let mut writer = Mock::new().limit(3).vectored();
+            let mut sink = (&mut writer)
+                .into_sink_with_capacity(2)
+                .with_vectored()
+                .with_length_delimited(LengthDelimited::new().u16())
+                .with_delimiter(Delimited::crlf());
+            sink.feed(Scatter(vec!["ab", "c"])).await.unwrap();
+            sink.feed(Scatter(vec!["", "d"])).await.unwrap();
+            sink.flush().await.unwrap();
+            assert_eq!(writer.written(), b"\0\x03abc\r\n\0\x01d\r\n");
+            assert_eq!(writer.calls, 4);
+        })
+    }
+
+    #[test]
+    fn stats() {
+        block_on(async {
+            let mut sink = Script::new([
+                Poll::Ready(Ok(2)),
+                Poll::Pending,
+                Poll::Ready(Ok(usize::MAX)),
+                Poll::Ready(Err(io::ErrorKind::BrokenPipe.into())),
+            ])
+            .into_sink()
+            .with_stats();
+            assert_eq!(sink.stats(), Some(Stats::default()));
+            sink.send("abcd").await.unwrap();
+            sink.send("ef").await.unwrap_err();
+            let stats = sink.stats().unwrap();
+            assert_eq!(stats.items, 2);
+            assert_eq!(stats.bytes_written, 4);
+            assert_eq!(stats.writes, 3);
+            assert_eq!(stats.partial_writes, 1);
+            assert_eq!(stats.flushes, 1);
+            assert_eq!(stats.errors, 1);
+        })
+    }
+
+    #[test]
+    fn stats_errors() {
+        paused(async {
+            let mut sink = Script::new([Poll::Ready(Ok(0))]).into_sink().with_stats();
+            sink.send("a").await.unwrap_err();
+            let stats = sink.stats().unwrap();
+            assert_eq!((stats.writes, stats.partial_writes), (1, 0));
+            assert_eq!(stats.errors, 1);
+
+            let mut sink = Mock::stuck()
+                .into_sink()
+                .with_timeouts(
+                    Timeouts::new()
+                        .flush(Duration::from_secs(1))
+                        .close(Duration::from_secs(1)),
+                )
+                .with_stats();
+            sink.feed("a").await.unwrap();
+            sink.flush().await.unwrap_err();
+            sink.close().await.unwrap_err();
+            let stats = sink.stats().unwrap();
+            assert_eq!(stats.errors, 2);
+            assert_eq!(stats.pending, Duration::from_secs(2));
+        })
+    }
+
+    #[test]
+    fn stats_pending() {
+        paused(async {
+            let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+            let mut sink = Mock::stuck().into_sink().with_stats();
+            sink.feed("a").await.unwrap();
+            assert!(sink.poll_flush_unpin(&mut cx).is_pending());
+            tokio::time::advance(Duration::from_secs(1)).await;
+            assert_eq!(sink.stats().unwrap().pending, Duration::from_secs(1));
+        })
+    }
+
+    #[test]
+    fn capacity_in_order() {
+        block_on(async {
+            let mut v = vec![];
+            let mut sink = (&mut v).into_sink_with_capacity(2);
+            for item in ["a", "b", "c", "d", "e"] {
+                sink.feed(item).await.unwrap();
+            }
+            sink.close().await.unwrap();
+            assert_eq!(v, b"abcde");
+        })
+    }
 }
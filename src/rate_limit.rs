@@ -0,0 +1,256 @@
+use std::{
+    io::IoSlice,
+    task::{ready, Context, Poll},
+    time::Duration,
+};
+
+use tokio::time::Instant;
+
+use crate::timer::Timer;
+
+const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+/// Limit the rate at which an [`IntoSink`](crate::IntoSink) writes, using a token bucket.
+///
+/// See [`IntoSink::with_rate_limit`](crate::IntoSink::with_rate_limit).
+///
+/// ```
+/// use tokio_into_sink::{IntoSinkExt as _, RateLimit};
+/// use futures::SinkExt as _;
+/// use std::time::Duration;
+///
+/// # tokio::runtime::Builder::new_current_thread().enable_time().start_paused(true).build().unwrap().block_on(async {
+/// let start = tokio::time::Instant::now();
+/// let mut v = vec![];
+/// let mut sink = (&mut v)
+///     .into_sink()
+///     .with_rate_limit(RateLimit::new(1024).burst(512));
+/// sink.send([0; 2048]).await.unwrap();
+/// assert_eq!(start.elapsed(), Duration::from_millis(1500));
+/// # }) // block_on
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RateLimit {
+    bytes_per_sec: u64,
+    burst: u64,
+}
+
+impl RateLimit {
+    /// Write at most `bytes_per_sec` on average, in bursts of up to the same size.
+    ///
+    /// # Panics
+    /// - If `bytes_per_sec` is zero.
+    pub fn new(bytes_per_sec: u64) -> Self {
+        assert!(bytes_per_sec > 0, "bytes_per_sec must be non-zero");
+        Self {
+            bytes_per_sec,
+            burst: bytes_per_sec,
+        }
+    }
+    /// Write at most `burst` bytes at once.
+    /// Larger items are split across several writes.
+    ///
+    /// # Panics
+    /// - If `burst` is zero.
+    pub fn burst(mut self, burst: u64) -> Self {
+        assert!(burst > 0, "burst must be non-zero");
+        self.burst = burst;
+        self
+    }
+}
+
+/// State for [`IntoSink::with_rate_limit`](crate::IntoSink::with_rate_limit).
+#[derive(Debug)]
+pub(crate) struct Throttle {
+    limit: RateLimit,
+    /// Bytes which may be written now.
+    tokens: u64,
+    /// When `tokens` were last topped up, set on first use.
+    refilled: Option<Instant>,
+    sleep: Timer,
+    /// Whether the last call to `poll_acquire` is waiting for tokens.
+    waiting: bool,
+}
+
+impl Throttle {
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            tokens: limit.burst,
+            refilled: None,
+            sleep: Timer::default(),
+            waiting: false,
+        }
+    }
+    fn refill(&mut self, now: Instant) {
+        let last = *self.refilled.get_or_insert(now);
+        let rate = u128::from(self.limit.bytes_per_sec);
+        let added = now.saturating_duration_since(last).as_nanos() * rate / NANOS_PER_SEC;
+        if u128::from(self.tokens) + added >= u128::from(self.limit.burst) {
+            self.tokens = self.limit.burst;
+            self.refilled = Some(now);
+        } else if added > 0 {
+            self.tokens += added as u64;
+            // keep credit for the part of a token which has accrued
+            self.refilled = Some(last + nanos(added * NANOS_PER_SEC / rate));
+        }
+    }
+    /// Wait until `want` bytes (or a whole burst) may be written, returning how many may be
+    /// written now.
+    pub fn poll_acquire(&mut self, cx: &mut Context<'_>, want: usize) -> Poll<usize> {
+        let want = (want as u64).min(self.limit.burst);
+        loop {
+            let now = Instant::now();
+            self.refill(now);
+            self.waiting = self.tokens < want;
+            if !self.waiting {
+                return Poll::Ready(self.tokens.try_into().unwrap_or(usize::MAX));
+            }
+            let deficit = u128::from(want - self.tokens);
+            let deadline = now
+                + nanos((deficit * NANOS_PER_SEC).div_ceil(u128::from(self.limit.bytes_per_sec)));
+            self.sleep.reset(deadline);
+            ready!(self.sleep.poll(cx));
+        }
+    }
+    /// Whether the last call to [`poll_acquire`](Self::poll_acquire) returned [`Poll::Pending`].
+    pub fn is_waiting(&self) -> bool {
+        self.waiting
+    }
+    /// Record that `written` bytes have been written.
+    pub fn consume(&mut self, written: usize) {
+        self.tokens = self.tokens.saturating_sub(written as u64)
+    }
+}
+
+fn nanos(nanos: u128) -> Duration {
+    Duration::from_nanos(nanos.try_into().unwrap_or(u64::MAX))
+}
+
+/// How many of `slices` to write, and how much of the last of those, to write at most `max`
+/// bytes.
+pub(crate) fn limit(slices: &[IoSlice<'_>], max: usize) -> (usize, usize) {
+    let mut total = 0;
+    for (ix, slice) in slices.iter().enumerate() {
+        if total + slice.len() >= max {
+            return (ix + 1, max - total);
+        }
+        total += slice.len();
+    }
+    (slices.len(), slices.last().map_or(0, |it| it.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{test_util::paused, IntoSinkExt as _};
+    use futures::SinkExt as _;
+    use std::{io, pin::Pin};
+    use tokio::io::AsyncWrite;
+
+    /// Records when each write happened, relative to when it was created.
+    struct Timed {
+        start: Instant,
+        writes: Vec<(Duration, usize)>,
+    }
+
+    impl AsyncWrite for Timed {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            let at = self.start.elapsed();
+            self.writes.push((at, buf.len()));
+            Poll::Ready(Ok(buf.len()))
+        }
+        fn poll_write_vectored(
+            mut self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+            bufs: &[IoSlice<'_>],
+        ) -> Poll<io::Result<usize>> {
+            let at = self.start.elapsed();
+            let len = bufs.iter().map(|it| it.len()).sum();
+            self.writes.push((at, len));
+            Poll::Ready(Ok(len))
+        }
+        fn is_write_vectored(&self) -> bool {
+            true
+        }
+        fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+        fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[test]
+    fn limit() {
+        let slices = [IoSlice::new(b"ab"), IoSlice::new(b"cd"), IoSlice::new(b"e")];
+        assert_eq!(super::limit(&slices, 3), (2, 1));
+        assert_eq!(super::limit(&slices, 4), (2, 2));
+        assert_eq!(super::limit(&slices, 10), (3, 1));
+    }
+
+    #[test]
+    fn split() {
+        paused(async {
+            let mut sink = Timed {
+                start: Instant::now(),
+                writes: vec![],
+            }
+            .into_sink()
+            .with_rate_limit(RateLimit::new(10).burst(4));
+            sink.send("abcdefghij").await.unwrap();
+            let ms = Duration::from_millis;
+            assert_eq!(
+                sink.get_ref().writes,
+                [(ms(0), 4), (ms(400), 4), (ms(600), 2)]
+            );
+        })
+    }
+
+    #[test]
+    fn vectored() {
+        paused(async {
+            let mut sink = Timed {
+                start: Instant::now(),
+                writes: vec![],
+            }
+            .into_sink_with_capacity(4)
+            .with_vectored()
+            .with_rate_limit(RateLimit::new(10).burst(3));
+            for item in ["ab", "cd", "ef"] {
+                sink.feed(item).await.unwrap();
+            }
+            sink.flush().await.unwrap();
+            let ms = Duration::from_millis;
+            assert_eq!(sink.get_ref().writes, [(ms(0), 3), (ms(300), 3)]);
+        })
+    }
+
+    #[test]
+    fn refill() {
+        paused(async {
+            let mut sink = Timed {
+                start: Instant::now(),
+                writes: vec![],
+            }
+            .into_sink()
+            .with_rate_limit(RateLimit::new(10));
+            sink.send("abcde").await.unwrap();
+            // half of the bucket is left
+            sink.send("abcde").await.unwrap();
+            // idle time tops the bucket up, but not past the burst size
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            sink.send("abcdefghijklmno").await.unwrap();
+            let ms = Duration::from_millis;
+            assert_eq!(
+                sink.get_ref().writes,
+                [(ms(0), 5), (ms(0), 5), (ms(5000), 10), (ms(5500), 5)]
+            );
+        })
+    }
+}
@@ -0,0 +1,367 @@
+use std::{
+    error::Error,
+    fmt,
+    future::Future,
+    io, mem,
+    pin::Pin,
+    task::{ready, Context, Poll},
+    time::Duration,
+};
+
+use futures_sink::Sink;
+use pin_project_lite::pin_project;
+use tokio::{io::AsyncWrite, time::Instant};
+
+use crate::{timer::Timer, Chunks, IntoSink};
+
+/// How a [`ReconnectingSink`] continues an item which was partly written when its writer failed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Resend {
+    /// Write the whole item to the new writer.
+    ///
+    /// This suits connections, where the peer discards a partial item.
+    #[default]
+    FromStart,
+    /// Write only the rest of the item to the new writer.
+    FromOffset,
+}
+
+/// When and how a [`ReconnectingSink`] replaces its writer.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Reconnect {
+    initial: Duration,
+    max: Duration,
+    retries: Option<u32>,
+    resend: Resend,
+}
+
+impl Default for Reconnect {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Reconnect {
+    /// Retry forever, waiting 100ms before the first attempt, doubling up to 10s,
+    /// and [resending items from the start](Resend::FromStart).
+    pub fn new() -> Self {
+        Self {
+            initial: Duration::from_millis(100),
+            max: Duration::from_secs(10),
+            retries: None,
+            resend: Resend::FromStart,
+        }
+    }
+    /// Wait `initial` before the first attempt to reconnect, doubling for each subsequent
+    /// attempt, up to `max`.
+    ///
+    /// # Panics
+    /// - If `initial` is greater than `max`.
+    pub fn backoff(mut self, initial: Duration, max: Duration) -> Self {
+        assert!(initial <= max, "initial backoff must not exceed max");
+        self.initial = initial;
+        self.max = max;
+        self
+    }
+    /// Give up after `retries` attempts to reconnect fail in a row,
+    /// rather than retrying forever.
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = Some(retries);
+        self
+    }
+    /// How to continue an item which was partly written when the writer failed.
+    pub fn resend(mut self, resend: Resend) -> Self {
+        self.resend = resend;
+        self
+    }
+}
+
+pin_project! {
+    /// An [`IntoSink`] which replaces its writer with a new one from `connect` when it fails,
+    /// waiting between attempts according to [`Reconnect`].
+    ///
+    /// Items which have not been written are kept, and written to the new writer.
+    /// If the [retries](Reconnect::retries) are exhausted, the last error is returned, and the
+    /// next call to the sink starts over.
+    ///
+    /// This uses [`tokio::time`], so must be used within a runtime with time enabled.
+    ///
+    /// ```
+    /// use tokio_into_sink::{IntoSinkExt as _, Reconnect, ReconnectingSink};
+    /// use futures::{stream, StreamExt as _};
+    /// use std::io;
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap().block_on(async {
+    /// let connect = || tokio::fs::File::create("/dev/null");
+    /// let sink = ReconnectingSink::new(
+    ///     connect().await.unwrap().into_sink(),
+    ///     connect,
+    ///     Reconnect::new().retries(5),
+    /// );
+    /// stream::iter(["hello", "world"])
+    ///     .map(io::Result::Ok)
+    ///     .forward(sink)
+    ///     .await
+    ///     .unwrap();
+    /// # }) // block_on
+    /// ```
+    #[derive(Debug)]
+    pub struct ReconnectingSink<W, Item, F, Fut> {
+        #[pin]
+        inner: IntoSink<W, Item>,
+        connect: F,
+        #[pin]
+        connecting: Option<Fut>,
+        reconnect: Reconnect,
+        backoff: Backoff,
+    }
+}
+
+impl<W, Item, F, Fut> ReconnectingSink<W, Item, F, Fut> {
+    /// Write items through `inner`, replacing its writer using `connect`.
+    ///
+    /// `inner`'s configuration is kept across writers.
+    pub fn new(inner: IntoSink<W, Item>, connect: F, reconnect: Reconnect) -> Self {
+        Self {
+            inner,
+            connect,
+            connecting: None,
+            reconnect,
+            backoff: Backoff {
+                attempts: 0,
+                waiting: false,
+                sleep: Timer::default(),
+            },
+        }
+    }
+    /// Get a reference to the underlying sink.
+    pub fn get_ref(&self) -> &IntoSink<W, Item> {
+        &self.inner
+    }
+    /// Get a mutable reference to the underlying sink.
+    pub fn get_mut(&mut self) -> &mut IntoSink<W, Item> {
+        &mut self.inner
+    }
+    /// Get a pinned mutable reference to the underlying sink.
+    pub fn get_pin_mut(self: Pin<&mut Self>) -> Pin<&mut IntoSink<W, Item>> {
+        self.project().inner
+    }
+    /// Consume this sink, returning the underlying sink.
+    pub fn into_inner(self) -> IntoSink<W, Item> {
+        self.inner
+    }
+}
+
+impl<W, Item, F, Fut> ReconnectingSink<W, Item, F, Fut>
+where
+    W: AsyncWrite,
+    Item: Chunks,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = io::Result<W>>,
+{
+    /// Drive `op` on the underlying sink to completion, reconnecting whenever it fails.
+    fn poll_reconnecting(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        mut op: impl FnMut(Pin<&mut IntoSink<W, Item>>, &mut Context<'_>) -> Poll<io::Result<()>>,
+    ) -> Poll<io::Result<()>> {
+        let mut this = self.project();
+        loop {
+            if this.backoff.waiting {
+                ready!(this.backoff.poll_wait(cx));
+                this.connecting.set(Some((this.connect)()));
+            }
+            if let Some(connecting) = this.connecting.as_mut().as_pin_mut() {
+                let res = ready!(connecting.poll(cx));
+                this.connecting.set(None);
+                match res {
+                    Ok(writer) => this
+                        .inner
+                        .as_mut()
+                        .replace_writer(writer, this.reconnect.resend == Resend::FromStart),
+                    Err(e) => {
+                        this.backoff.failed(this.reconnect, e)?;
+                        continue;
+                    }
+                }
+            }
+            match ready!(op(this.inner.as_mut(), cx)) {
+                Ok(()) => {
+                    this.backoff.attempts = 0;
+                    return Poll::Ready(Ok(()));
+                }
+                Err(e) => this.backoff.failed(this.reconnect, e)?,
+            }
+        }
+    }
+}
+
+impl<W, Item, F, Fut> Sink<Item> for ReconnectingSink<W, Item, F, Fut>
+where
+    W: AsyncWrite,
+    Item: Chunks,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = io::Result<W>>,
+{
+    type Error = io::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        // a new writer won't help a caller which has broken the protocol
+        if self.inner.is_closing() {
+            return self.project().inner.poll_ready(cx);
+        }
+        self.poll_reconnecting(cx, |inner, cx| inner.poll_ready(cx))
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: Item) -> Result<(), Self::Error> {
+        self.project().inner.start_send(item)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.poll_reconnecting(cx, |inner, cx| inner.poll_flush(cx))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.poll_reconnecting(cx, |inner, cx| inner.poll_close(cx))
+    }
+}
+
+/// Waits between attempts to reconnect.
+#[derive(Debug)]
+struct Backoff {
+    /// Attempts to reconnect since the sink last succeeded.
+    attempts: u32,
+    waiting: bool,
+    sleep: Timer,
+}
+
+impl Backoff {
+    /// Start waiting to reconnect after `error`, or give up.
+    fn failed(&mut self, reconnect: &Reconnect, error: io::Error) -> io::Result<()> {
+        if reconnect.retries.is_some_and(|it| self.attempts >= it) {
+            let attempts = mem::take(&mut self.attempts);
+            return Err(io::Error::new(error.kind(), GaveUp { attempts, error }));
+        }
+        let delay = reconnect
+            .initial
+            .saturating_mul(1 << self.attempts.min(31))
+            .min(reconnect.max);
+        self.attempts += 1;
+        self.sleep.reset(Instant::now() + delay);
+        self.waiting = true;
+        Ok(())
+    }
+    fn poll_wait(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        ready!(self.sleep.poll(cx));
+        self.waiting = false;
+        Poll::Ready(())
+    }
+}
+
+/// The error returned when a [`ReconnectingSink`] runs out of retries.
+#[derive(Debug)]
+struct GaveUp {
+    attempts: u32,
+    error: io::Error,
+}
+
+impl fmt::Display for GaveUp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "gave up reconnecting after {} attempts", self.attempts)
+    }
+}
+
+impl Error for GaveUp {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        test_util::{paused, Mock},
+        IntoSinkExt as _,
+    };
+    use futures::SinkExt as _;
+    use std::collections::VecDeque;
+
+    /// Hands out `conns` in order, and then refuses to connect.
+    fn connect(
+        conns: impl IntoIterator<Item = Mock>,
+    ) -> impl FnMut() -> std::future::Ready<io::Result<Mock>> {
+        let mut conns = conns.into_iter().collect::<VecDeque<_>>();
+        move || {
+            std::future::ready(
+                conns
+                    .pop_front()
+                    .ok_or_else(|| io::ErrorKind::ConnectionRefused.into()),
+            )
+        }
+    }
+
+    #[test]
+    fn resend() {
+        for (resend, expected) in [
+            (Resend::FromStart, &b"hello world"[..]),
+            (Resend::FromOffset, b"lo world"),
+        ] {
+            paused(async {
+                let start = Instant::now();
+                let first = Mock::new().fail_after(3);
+                let second = Mock::new();
+                let mut sink = ReconnectingSink::new(
+                    first.clone().into_sink(),
+                    connect([second.clone()]),
+                    Reconnect::new().resend(resend),
+                );
+                sink.send("hello").await.unwrap();
+                sink.send(" world").await.unwrap();
+                assert_eq!(start.elapsed(), Duration::from_millis(100));
+                assert_eq!(first.written(), b"hel");
+                assert_eq!(second.written(), expected);
+            })
+        }
+    }
+
+    #[test]
+    fn backoff() {
+        paused(async {
+            let start = Instant::now();
+            let second = Mock::new();
+            let mut sink = ReconnectingSink::new(
+                Mock::new().fail_after(0).into_sink(),
+                // the connections in between fail immediately
+                connect([
+                    Mock::new().fail_after(0),
+                    Mock::new().fail_after(0),
+                    second.clone(),
+                ]),
+                Reconnect::new().backoff(Duration::from_secs(1), Duration::from_secs(3)),
+            );
+            sink.send("hello").await.unwrap();
+            assert_eq!(start.elapsed(), Duration::from_secs(1 + 2 + 3));
+            assert_eq!(second.written(), b"hello");
+        })
+    }
+
+    #[test]
+    fn give_up() {
+        paused(async {
+            let start = Instant::now();
+            let mut sink = ReconnectingSink::new(
+                Mock::new().fail_after(0).into_sink(),
+                connect([]),
+                Reconnect::new().retries(2),
+            );
+            let error = sink.send("hello").await.unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::ConnectionRefused);
+            assert_eq!(error.to_string(), "gave up reconnecting after 2 attempts");
+            assert_eq!(start.elapsed(), Duration::from_millis(100 + 200));
+            // the item is kept
+            assert_eq!(sink.get_ref().unwritten_bytes(), 5);
+        })
+    }
+}
@@ -0,0 +1,153 @@
+use std::{
+    io,
+    marker::PhantomData,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use futures_sink::Sink;
+use pin_project_lite::pin_project;
+use tokio::io::AsyncWrite;
+
+pin_project! {
+    /// Use a [`Sink`] of bytes as an [`AsyncWrite`] - the reverse of [`IntoSink`](crate::IntoSink).
+    ///
+    /// Each write is copied into a new item of at most [`chunk_size`](Self::with_chunk_size)
+    /// bytes, and sent to the sink.
+    /// [`AsyncWrite::poll_flush`] and [`AsyncWrite::poll_shutdown`] map to [`Sink::poll_flush`]
+    /// and [`Sink::poll_close`].
+    ///
+    /// ```
+    /// use tokio_into_sink::SinkWriter;
+    /// use futures::{channel::mpsc, SinkExt as _};
+    /// use std::io;
+    ///
+    /// let (tx, rx) = mpsc::unbounded::<Vec<u8>>();
+    /// let writer: SinkWriter<_> = SinkWriter::new(tx.sink_map_err(io::Error::other));
+    /// # drop((writer, rx));
+    /// ```
+    #[derive(Debug)]
+    pub struct SinkWriter<S, Item = Vec<u8>> {
+        #[pin]
+        sink: S,
+        chunk_size: usize,
+        item: PhantomData<fn(Item)>,
+    }
+}
+
+impl<S, Item> SinkWriter<S, Item> {
+    /// Write to `sink`, sending each write as a single item.
+    pub fn new(sink: S) -> Self {
+        Self {
+            sink,
+            chunk_size: usize::MAX,
+            item: PhantomData,
+        }
+    }
+    /// Send at most `chunk_size` bytes in each item.
+    ///
+    /// # Panics
+    /// - If `chunk_size` is zero.
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        assert!(chunk_size > 0, "chunk_size must be non-zero");
+        self.chunk_size = chunk_size;
+        self
+    }
+    /// Get a reference to the underlying sink.
+    pub fn get_ref(&self) -> &S {
+        &self.sink
+    }
+    /// Get a mutable reference to the underlying sink.
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.sink
+    }
+    /// Get a pinned mutable reference to the underlying sink.
+    pub fn get_pin_mut(self: Pin<&mut Self>) -> Pin<&mut S> {
+        self.project().sink
+    }
+    /// Consume this writer, returning the underlying sink.
+    pub fn into_inner(self) -> S {
+        self.sink
+    }
+}
+
+impl<S, Item> AsyncWrite for SinkWriter<S, Item>
+where
+    S: Sink<Item>,
+    S::Error: Into<io::Error>,
+    Item: From<Vec<u8>>,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+        let mut this = self.project();
+        ready!(this.sink.as_mut().poll_ready(cx)).map_err(Into::into)?;
+        let len = buf.len().min(*this.chunk_size);
+        this.sink
+            .start_send(Item::from(buf[..len].to_vec()))
+            .map_err(Into::into)?;
+        Poll::Ready(Ok(len))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().sink.poll_flush(cx).map_err(Into::into)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().sink.poll_close(cx).map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::IntoSinkExt as _;
+    use futures::{channel::mpsc, SinkExt as _, StreamExt as _};
+
+    #[test]
+    fn chunks() {
+        let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+        let (tx, rx) = mpsc::unbounded();
+        let mut writer =
+            SinkWriter::<_, Vec<u8>>::new(tx.sink_map_err(io::Error::other)).with_chunk_size(3);
+        let mut writer = Pin::new(&mut writer);
+        assert!(matches!(
+            writer.as_mut().poll_write(&mut cx, b"abcde"),
+            Poll::Ready(Ok(3))
+        ));
+        assert!(matches!(
+            writer.as_mut().poll_write(&mut cx, b"de"),
+            Poll::Ready(Ok(2))
+        ));
+        assert!(matches!(
+            writer.as_mut().poll_write(&mut cx, b""),
+            Poll::Ready(Ok(0))
+        ));
+        assert!(writer.as_mut().poll_shutdown(&mut cx).is_ready());
+        let items = futures::executor::block_on(rx.collect::<Vec<_>>());
+        assert_eq!(items, [b"abc".to_vec(), b"de".to_vec()]);
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+        let mut v = vec![];
+        let mut writer = SinkWriter::<_, Vec<u8>>::new((&mut v).into_sink());
+        assert!(matches!(
+            Pin::new(&mut writer).poll_write(&mut cx, b"hello"),
+            Poll::Ready(Ok(5))
+        ));
+        assert!(matches!(
+            Pin::new(&mut writer).poll_flush(&mut cx),
+            Poll::Ready(Ok(()))
+        ));
+        drop(writer);
+        assert_eq!(v, b"hello");
+    }
+}
@@ -0,0 +1,103 @@
+use std::{io, task::Poll, time::Duration};
+
+use tokio::time::Instant;
+
+/// Counters for an [`IntoSink`](crate::IntoSink).
+///
+/// See [`IntoSink::with_stats`](crate::IntoSink::with_stats).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Stats {
+    /// Items accepted by [`Sink::start_send`](futures_sink::Sink::start_send).
+    pub items: u64,
+    /// Bytes accepted by the writer.
+    pub bytes_written: u64,
+    /// Calls to [`AsyncWrite::poll_write`](tokio::io::AsyncWrite::poll_write) or
+    /// [`AsyncWrite::poll_write_vectored`](tokio::io::AsyncWrite::poll_write_vectored)
+    /// which completed.
+    pub writes: u64,
+    /// Writes which accepted fewer bytes than they were given.
+    pub partial_writes: u64,
+    /// Completed calls to [`AsyncWrite::poll_flush`](tokio::io::AsyncWrite::poll_flush).
+    pub flushes: u64,
+    /// Time spent waiting on the writer to write, flush or shut down.
+    pub pending: Duration,
+    /// Errors returned by the writer, including writes which accepted nothing, and
+    /// [timeouts](crate::Timeouts).
+    pub errors: u64,
+}
+
+/// Updates [`Stats`] as an [`IntoSink`](crate::IntoSink) calls into its writer.
+#[derive(Debug, Default)]
+pub(crate) struct Recorder {
+    stats: Stats,
+    /// When the writer first returned [`Poll::Pending`] for the current operation.
+    pending_since: Option<Instant>,
+}
+
+impl Recorder {
+    pub fn snapshot(&self) -> Stats {
+        let mut stats = self.stats;
+        if let Some(since) = self.pending_since {
+            stats.pending += since.elapsed()
+        }
+        stats
+    }
+    pub fn item(&mut self) {
+        self.stats.items += 1
+    }
+    /// Record the outcome of calling into the writer.
+    pub fn poll<T>(&mut self, poll: Poll<io::Result<T>>) -> Poll<io::Result<T>> {
+        match &poll {
+            Poll::Pending => {
+                self.pending_since.get_or_insert_with(Instant::now);
+            }
+            Poll::Ready(res) => {
+                if let Some(since) = self.pending_since.take() {
+                    self.stats.pending += since.elapsed()
+                }
+                if res.is_err() {
+                    self.stats.errors += 1
+                }
+            }
+        }
+        poll
+    }
+    pub fn write(&mut self, offered: usize, res: &io::Result<usize>) {
+        self.stats.writes += 1;
+        if let Ok(written) = res {
+            self.stats.bytes_written += *written as u64;
+            if *written < offered {
+                self.stats.partial_writes += 1
+            }
+        }
+    }
+    pub fn flush(&mut self) {
+        self.stats.flushes += 1
+    }
+    /// Record that the current operation has been abandoned.
+    pub fn timed_out(&mut self) {
+        if let Some(since) = self.pending_since.take() {
+            self.stats.pending += since.elapsed()
+        }
+        self.stats.errors += 1
+    }
+}
+
+/// Call `f` if stats are enabled.
+pub(crate) fn record<T>(recorder: &mut Option<Recorder>, f: impl FnOnce(&mut Recorder) -> T) {
+    if let Some(recorder) = recorder {
+        f(recorder);
+    }
+}
+
+/// Record the outcome of calling into the writer, if stats are enabled.
+pub(crate) fn poll<T>(
+    recorder: &mut Option<Recorder>,
+    poll: Poll<io::Result<T>>,
+) -> Poll<io::Result<T>> {
+    match recorder {
+        Some(recorder) => recorder.poll(poll),
+        None => poll,
+    }
+}
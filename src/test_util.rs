@@ -0,0 +1,188 @@
+//! Writers, readers and runtimes shared by the tests.
+
+use std::{
+    future::Future,
+    io::{self, IoSlice},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+/// Run `f` on a runtime with a paused clock, which skips ahead whenever every task is waiting on
+/// a timer.
+pub fn paused<F: Future>(f: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .start_paused(true)
+        .build()
+        .unwrap()
+        .block_on(f)
+}
+
+/// What a [`Mock`] does once it has accepted `ok` bytes.
+#[derive(Debug, Clone, Copy)]
+enum Then {
+    /// Fail writes with [`io::ErrorKind::BrokenPipe`].
+    Fail,
+    /// Return [`Poll::Pending`] from every call, without waking.
+    Pend,
+}
+
+/// A writer which accepts at most `limit` bytes per write, and `ok` bytes in total,
+/// and then fails or hangs.
+///
+/// Clones share what has been [`written`](Self::written).
+#[derive(Debug, Clone)]
+pub struct Mock {
+    pub limit: usize,
+    pub ok: usize,
+    then: Then,
+    vectored: bool,
+    /// Calls to [`AsyncWrite::poll_write`] or [`AsyncWrite::poll_write_vectored`].
+    pub calls: usize,
+    /// Completed calls to [`AsyncWrite::poll_flush`].
+    pub flushes: usize,
+    written: Arc<Mutex<Vec<u8>>>,
+}
+
+impl Mock {
+    /// A writer which accepts everything.
+    pub fn new() -> Self {
+        Self {
+            limit: usize::MAX,
+            ok: usize::MAX,
+            then: Then::Fail,
+            vectored: false,
+            calls: 0,
+            flushes: 0,
+            written: Arc::default(),
+        }
+    }
+    /// A writer which never makes progress.
+    pub fn stuck() -> Self {
+        Self::new().pend_after(0)
+    }
+    /// Accept at most `limit` bytes per write.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+    /// Fail once `ok` bytes have been accepted.
+    pub fn fail_after(mut self, ok: usize) -> Self {
+        self.ok = ok;
+        self.then = Then::Fail;
+        self
+    }
+    /// Hang once `ok` bytes have been accepted, including flushes and shutdowns.
+    pub fn pend_after(mut self, ok: usize) -> Self {
+        self.ok = ok;
+        self.then = Then::Pend;
+        self
+    }
+    /// Support [`AsyncWrite::poll_write_vectored`].
+    pub fn vectored(mut self) -> Self {
+        self.vectored = true;
+        self
+    }
+    /// Everything which has been accepted.
+    pub fn written(&self) -> Vec<u8> {
+        self.written.lock().unwrap().clone()
+    }
+    fn hung(&self) -> bool {
+        matches!(self.then, Then::Pend) && self.ok == 0
+    }
+}
+
+impl AsyncWrite for Mock {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.poll_write_vectored(cx, &[IoSlice::new(buf)])
+    }
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        self.calls += 1;
+        if self.hung() {
+            return Poll::Pending;
+        }
+        // like the default implementation, write the first non-empty buffer
+        let bufs = match (self.vectored, bufs.iter().position(|it| !it.is_empty())) {
+            (false, Some(ix)) => &bufs[ix..=ix],
+            _ => bufs,
+        };
+        let this = &mut *self;
+        let mut n = 0;
+        let mut written = this.written.lock().unwrap();
+        for buf in bufs {
+            let take = buf.len().min(this.limit - n).min(this.ok - n);
+            written.extend_from_slice(&buf[..take]);
+            n += take;
+        }
+        this.ok -= n;
+        match n {
+            // only `Then::Fail` gets here without accepting anything
+            0 if bufs.iter().any(|it| !it.is_empty()) => {
+                Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()))
+            }
+            n => Poll::Ready(Ok(n)),
+        }
+    }
+    fn is_write_vectored(&self) -> bool {
+        self.vectored
+    }
+    fn poll_flush(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        if self.hung() {
+            return Poll::Pending;
+        }
+        self.flushes += 1;
+        Poll::Ready(Ok(()))
+    }
+    fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.hung() {
+            true => Poll::Pending,
+            false => Poll::Ready(Ok(())),
+        }
+    }
+}
+
+/// A reader which returns [`Poll::Pending`] before every read, and reads a byte at a time.
+#[derive(Debug)]
+pub struct Stutter {
+    bytes: &'static [u8],
+    pending: bool,
+}
+
+impl Stutter {
+    pub fn new(bytes: &'static [u8]) -> Self {
+        Self {
+            bytes,
+            pending: true,
+        }
+    }
+}
+
+impl AsyncRead for Stutter {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        self.pending = !self.pending;
+        if !self.pending {
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+        if let Some((first, rest)) = self.bytes.split_first() {
+            buf.put_slice(&[*first]);
+            self.bytes = rest;
+        }
+        Poll::Ready(Ok(()))
+    }
+}
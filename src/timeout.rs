@@ -0,0 +1,204 @@
+use std::{
+    io,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use tokio::time::Instant;
+
+use crate::timer::Timer;
+
+/// How long an [`IntoSink`](crate::IntoSink) may wait on its writer before failing with
+/// [`io::ErrorKind::TimedOut`].
+///
+/// See [`IntoSink::with_timeouts`](crate::IntoSink::with_timeouts).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Timeouts {
+    item: Option<Duration>,
+    flush: Option<Duration>,
+    close: Option<Duration>,
+}
+
+impl Timeouts {
+    /// No timeouts.
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Fail if an item has not been completely written `timeout` after the writer first returned
+    /// [`Poll::Pending`] while writing it.
+    ///
+    /// Partial writes do not extend the deadline, so a slow writer may time out while it is still
+    /// making progress.
+    /// Waiting on a [rate limit](crate::IntoSink::with_rate_limit) does not start the deadline.
+    ///
+    /// The item is handed back by [`SendError`](crate::SendError)s, or remains queued otherwise.
+    pub fn item(mut self, timeout: Duration) -> Self {
+        self.item = Some(timeout);
+        self
+    }
+    /// Fail if [`Sink::poll_flush`](futures_sink::Sink::poll_flush) has not completed `timeout`
+    /// after it first returned [`Poll::Pending`].
+    ///
+    /// Unwritten items remain queued.
+    pub fn flush(mut self, timeout: Duration) -> Self {
+        self.flush = Some(timeout);
+        self
+    }
+    /// Fail if [`Sink::poll_close`](futures_sink::Sink::poll_close) has not completed `timeout`
+    /// after it first returned [`Poll::Pending`].
+    ///
+    /// Unwritten items remain queued.
+    pub fn close(mut self, timeout: Duration) -> Self {
+        self.close = Some(timeout);
+        self
+    }
+}
+
+/// State for [`IntoSink::with_timeouts`](crate::IntoSink::with_timeouts).
+#[derive(Debug)]
+pub(crate) struct Deadlines {
+    pub item: Option<Deadline>,
+    pub flush: Option<Deadline>,
+    pub close: Option<Deadline>,
+}
+
+impl Deadlines {
+    pub fn new(timeouts: Timeouts) -> Self {
+        Self {
+            item: timeouts.item.map(Deadline::new),
+            flush: timeouts.flush.map(Deadline::new),
+            close: timeouts.close.map(Deadline::new),
+        }
+    }
+}
+
+/// A deadline which starts when an operation is first pending.
+#[derive(Debug)]
+pub(crate) struct Deadline {
+    timeout: Duration,
+    armed: bool,
+    sleep: Timer,
+}
+
+impl Deadline {
+    fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            armed: false,
+            sleep: Timer::default(),
+        }
+    }
+    /// Call when the operation is pending, starting the deadline if required.
+    ///
+    /// Returns whether the deadline has passed, after which it is reset.
+    pub fn elapsed(&mut self, cx: &mut Context<'_>) -> bool {
+        if !self.armed {
+            self.sleep.reset(Instant::now() + self.timeout);
+            self.armed = true;
+        }
+        match self.sleep.poll(cx) {
+            Poll::Ready(()) => {
+                self.armed = false;
+                true
+            }
+            Poll::Pending => false,
+        }
+    }
+    /// Call when the operation has completed.
+    pub fn reset(&mut self) {
+        self.armed = false
+    }
+    /// Whether the operation which returned `poll` has timed out.
+    pub fn check<T>(&mut self, cx: &mut Context<'_>, poll: &Poll<T>) -> bool {
+        match poll {
+            Poll::Ready(_) => {
+                self.reset();
+                false
+            }
+            Poll::Pending => self.elapsed(cx),
+        }
+    }
+}
+
+pub(crate) fn timed_out(message: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::TimedOut, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        test_util::{paused, Mock},
+        IntoSinkExt as _, RateLimit, Unwritten,
+    };
+    use futures::SinkExt as _;
+
+    #[test]
+    fn item() {
+        paused(async {
+            let start = Instant::now();
+            let writer = Mock::new().pend_after(3);
+            let mut sink = writer
+                .clone()
+                .into_sink()
+                .with_timeouts(Timeouts::new().item(Duration::from_secs(1)))
+                .with_send_error();
+            sink.feed("ab").await.unwrap();
+            sink.feed("cd").await.unwrap();
+            let error = sink.feed("ef").await.unwrap_err();
+            assert_eq!(error.io_error().kind(), io::ErrorKind::TimedOut);
+            assert_eq!(start.elapsed(), Duration::from_secs(1));
+            let Some(Unwritten::Item { item, written, .. }) = error.unwritten() else {
+                panic!()
+            };
+            assert_eq!((*item, *written), ("cd", 1));
+            // the sink may be used again, with a new deadline
+            sink.feed("ef").await.unwrap();
+            let error = sink.feed("gh").await.unwrap_err();
+            assert_eq!(error.io_error().kind(), io::ErrorKind::TimedOut);
+            assert_eq!(start.elapsed(), Duration::from_secs(2));
+            assert_eq!(writer.written(), b"abc");
+        })
+    }
+
+    #[test]
+    fn flush_and_close() {
+        paused(async {
+            let start = Instant::now();
+            let mut sink = Mock::new()
+                .pend_after(2)
+                .into_sink_with_capacity(2)
+                .with_timeouts(
+                    Timeouts::new()
+                        .flush(Duration::from_secs(1))
+                        .close(Duration::from_secs(2)),
+                );
+            sink.feed("ab").await.unwrap();
+            let error = sink.flush().await.unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+            assert_eq!(start.elapsed(), Duration::from_secs(1));
+            sink.feed("cd").await.unwrap();
+            let error = sink.close().await.unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+            assert_eq!(start.elapsed(), Duration::from_secs(3));
+            assert_eq!(sink.unwritten_bytes(), 2);
+        })
+    }
+
+    #[test]
+    fn rate_limit() {
+        paused(async {
+            let start = Instant::now();
+            let writer = Mock::new();
+            let mut sink = writer
+                .clone()
+                .into_sink()
+                .with_rate_limit(RateLimit::new(1).burst(1))
+                .with_timeouts(Timeouts::new().item(Duration::from_secs(2)));
+            sink.send("abcdefghij").await.unwrap();
+            assert_eq!(start.elapsed(), Duration::from_secs(9));
+            assert_eq!(writer.written(), b"abcdefghij");
+        })
+    }
+}